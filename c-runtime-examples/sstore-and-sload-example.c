@@ -1,11 +1,21 @@
 #include "syscalls.h"
 
 void main() {
-    sys_sstore(42, 0xdeadbeef);
+    uint8_t key[32] = {0};
+    uint8_t value[32] = {0};
+    key[31] = 42;
+    value[28] = 0xde;
+    value[29] = 0xad;
+    value[30] = 0xbe;
+    value[31] = 0xef;
+    sys_sstore(key, value);
 
-    uint64_t value = sys_sload(42);
-    if (value != 0xdeadbeef) {
-        sys_revert();
+    uint8_t loaded[32];
+    sys_sload(key, loaded);
+    for (int i = 0; i < 32; i++) {
+        if (loaded[i] != value[i]) {
+            sys_revert((void*)0, 0);
+        }
     }
     sys_return((void*)0, 0);
 }
@@ -14,22 +14,22 @@ void sys_return(void* data, uint64_t length) {
 }
 
 // Wrapper for the `sload` syscall
-uint64_t sys_sload(uint64_t key) {
-    register uint64_t a0 asm("a0") = key;
+void sys_sload(const uint8_t key[32], uint8_t value[32]) {
+    register uint64_t a0 asm("a0") = (uint64_t)key;
+    register uint64_t a1 asm("a1") = (uint64_t)value;
     register uint64_t t0 asm("t0") = SYS_SLOAD;
     asm volatile (
         "ecall"
-        : "+r" (a0)
-        : "r" (t0)
+        :
+        : "r" (a0), "r" (a1), "r" (t0)
         : "memory"
     );
-    return a0;
 }
 
 // Wrapper for the `sstore` syscall
-void sys_sstore(uint64_t key, uint64_t value) {
-    register uint64_t a0 asm("a0") = key;
-    register uint64_t a1 asm("a1") = value;
+void sys_sstore(const uint8_t key[32], const uint8_t value[32]) {
+    register uint64_t a0 asm("a0") = (uint64_t)key;
+    register uint64_t a1 asm("a1") = (uint64_t)value;
     register uint64_t t0 asm("t0") = SYS_SSTORE;
     asm volatile (
         "ecall"
@@ -51,12 +51,14 @@ void sys_call() {
 }
 
 // Wrapper for the `revert` syscall
-void sys_revert() {
+void sys_revert(void* data, uint64_t length) {
+    register uint64_t a0 asm("a0") = (uint64_t)data;
+    register uint64_t a1 asm("a1") = length;
     register uint64_t t0 asm("t0") = SYS_REVERT;
     asm volatile (
         "ecall"
         :
-        : "r" (t0)
+        : "r" (a0), "r" (a1), "r" (t0)
         : "memory"
     );
 }
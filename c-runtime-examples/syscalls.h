@@ -23,18 +23,18 @@ void sys_return(void* data, uint64_t length);
 /**
  * @brief Wrapper for the `sload` syscall
  * 
- * @param key Storage key to load the value from
- * @return uint64_t Value stored at the given key
+ * @param key 32-byte big-endian storage key to load the value from
+ * @param value Buffer receiving the 32-byte big-endian value stored at the given key
  */
-uint64_t sys_sload(uint64_t key);
+void sys_sload(const uint8_t key[32], uint8_t value[32]);
 
 /**
  * @brief Wrapper for the `sstore` syscall
  * 
- * @param key Storage key to store the value at
- * @param value Value to be stored
+ * @param key 32-byte big-endian storage key to store the value at
+ * @param value 32-byte big-endian value to be stored
  */
-void sys_sstore(uint64_t key, uint64_t value);
+void sys_sstore(const uint8_t key[32], const uint8_t value[32]);
 
 /**
  * @brief Wrapper for the `call` syscall
@@ -45,7 +45,10 @@ void sys_call();
 
 /**
  * @brief Wrapper for the `revert` syscall
+ * 
+ * @param data Pointer to the revert data, e.g. an ABI-encoded error
+ * @param length Length of the data in bytes
  */
-void sys_revert();
+void sys_revert(void* data, uint64_t length);
 
 #endif // SYSCALL_WRAPPERS_H
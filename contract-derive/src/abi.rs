@@ -0,0 +1,241 @@
+//! Parsing of Solidity ABI JSON files for `abi_json!`.
+
+use std::collections::HashMap;
+
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use serde_json::Value;
+use syn::Signature;
+
+use crate::selector;
+
+/// Returns the signature of the client method and the selector of each function in the ABI
+/// `json`.
+pub(crate) fn parse_functions(json: &str) -> (Vec<Signature>, Vec<u32>) {
+    let json: Value = serde_json::from_str(json).expect("Invalid ABI JSON.");
+    // Build artifacts, e.g. from solc or Foundry, have the ABI in their `abi` field.
+    let items = match json.get("abi").unwrap_or(&json) {
+        Value::Array(items) => items,
+        _ => panic!("Expected an ABI array."),
+    };
+
+    let mut names = HashMap::new();
+    items
+        .iter()
+        .filter(|item| item["type"] == "function")
+        .map(|item| {
+            let name = item["name"].as_str().expect("Function without a name.");
+            let inputs = params(&item["inputs"]);
+            let outputs = params(&item["outputs"]);
+
+            let input_types: Vec<_> = inputs.iter().copied().map(canonical_type).collect();
+            let selector = selector(&format!("{}({})", name, input_types.join(",")));
+
+            // Overloaded functions can't share the same Rust method name.
+            let count = names.entry(name).or_insert(0);
+            let mut method_name = to_snake_case(name);
+            if *count > 0 {
+                method_name = format!("{}_{}", method_name, count);
+            }
+            *count += 1;
+            let method_name = format_ident!("{}", method_name);
+
+            let receiver = match item["stateMutability"].as_str() {
+                Some("view") | Some("pure") => quote! { &self },
+                Some(_) => quote! { &mut self },
+                // ABIs from before Solidity 0.4.16 only have `constant`.
+                None if item["constant"] == true => quote! { &self },
+                None => quote! { &mut self },
+            };
+            let arg_types = inputs.iter().copied().map(rust_type);
+            let arg_names = (0..inputs.len()).map(|i| format_ident!("arg{}", i));
+            let output = match outputs.as_slice() {
+                [] => quote! {},
+                [output] => {
+                    let ty = rust_type(output);
+                    quote! { -> #ty }
+                }
+                outputs => {
+                    let types = outputs.iter().copied().map(rust_type);
+                    quote! { -> (#( #types, )*) }
+                }
+            };
+
+            let sig = syn::parse2(quote! {
+                fn #method_name(#receiver, #( #arg_names: #arg_types ),*) #output
+            })
+            .expect("Failed to generate the method signature.");
+            (sig, selector)
+        })
+        .unzip()
+}
+
+fn params(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(params) => params.iter().collect(),
+        Value::Null => Vec::new(),
+        _ => panic!("Expected an array of parameters."),
+    }
+}
+
+/// Splits an ABI type such as `uint256[2][]` into its base type and its array suffixes.
+fn split_array_suffixes(ty: &str) -> (&str, Vec<Option<usize>>) {
+    let base_end = ty.find('[').unwrap_or(ty.len());
+    let suffixes = ty[base_end..]
+        .split_terminator(']')
+        .map(|suffix| {
+            let len = suffix.trim_start_matches('[');
+            if len.is_empty() {
+                None
+            } else {
+                Some(len.parse().expect("Invalid array length."))
+            }
+        })
+        .collect();
+    (&ty[..base_end], suffixes)
+}
+
+/// Returns the canonical type of `param` used in function signatures, expanding tuples.
+fn canonical_type(param: &Value) -> String {
+    let ty = param["type"].as_str().expect("Parameter without a type.");
+    let (base, _) = split_array_suffixes(ty);
+    if base == "tuple" {
+        let components: Vec<_> = params(&param["components"])
+            .into_iter()
+            .map(canonical_type)
+            .collect();
+        format!("({}){}", components.join(","), &ty[base.len()..])
+    } else {
+        ty.to_string()
+    }
+}
+
+/// Returns the Rust type `param` is ABI-encoded from, with the same mapping as `sol_type`.
+fn rust_type(param: &Value) -> TokenStream {
+    let ty = param["type"].as_str().expect("Parameter without a type.");
+    let (base, suffixes) = split_array_suffixes(ty);
+    let mut rust_type = match base {
+        "tuple" => {
+            let components = params(&param["components"]).into_iter().map(rust_type);
+            quote! { (#( #components, )*) }
+        }
+        "address" => quote! { alloy_core::primitives::Address },
+        "bool" => quote! { bool },
+        "string" => quote! { alloc::string::String },
+        "bytes" => quote! { alloy_core::primitives::Bytes },
+        "function" => quote! { alloy_core::primitives::FixedBytes<24> },
+        _ => {
+            if let Some(bits) = base.strip_prefix("uint") {
+                int_type("u", "U", bits)
+            } else if let Some(bits) = base.strip_prefix("int") {
+                int_type("i", "I", bits)
+            } else if let Some(size) = base.strip_prefix("bytes") {
+                let size: usize = size.parse().expect("Invalid fixed bytes size.");
+                quote! { alloy_core::primitives::FixedBytes<#size> }
+            } else {
+                panic!("Unsupported ABI type `{}`.", ty);
+            }
+        }
+    };
+    for suffix in suffixes {
+        rust_type = match suffix {
+            Some(len) => quote! { [#rust_type; #len] },
+            None => quote! { alloc::vec::Vec<#rust_type> },
+        };
+    }
+    rust_type
+}
+
+/// Maps an integer type to a Rust primitive if there is one of the same size, and to an alloy
+/// alias such as `U256` otherwise.
+fn int_type(primitive_prefix: &str, alias_prefix: &str, bits: &str) -> TokenStream {
+    let bits = if bits.is_empty() { "256" } else { bits };
+    match bits {
+        "8" | "16" | "32" | "64" | "128" => {
+            let ident = format_ident!("{}{}", primitive_prefix, bits);
+            quote! { #ident }
+        }
+        _ => {
+            let ident = format_ident!("{}{}", alias_prefix, bits);
+            quote! { alloy_core::primitives::aliases::#ident }
+        }
+    }
+}
+
+/// Converts a Solidity function name to the Rust naming convention, e.g. `getReserves` to
+/// `get_reserves` and `DOMAIN_SEPARATOR` to `domain_separator`.
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 && !result.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map_or(false, |next| next.is_lowercase());
+            if prev.is_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_uppercase() && next_is_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_functions() {
+        let json = r#"[
+            {"type": "constructor", "inputs": []},
+            {"type": "function", "name": "getReserves", "inputs": [], "outputs": [
+                {"name": "reserve0", "type": "uint112"},
+                {"name": "reserve1", "type": "uint112"},
+                {"name": "blockTimestampLast", "type": "uint32"}
+            ], "stateMutability": "view"},
+            {"type": "function", "name": "transfer", "inputs": [
+                {"name": "to", "type": "address"},
+                {"name": "value", "type": "uint256"}
+            ], "outputs": [{"name": "", "type": "bool"}], "stateMutability": "nonpayable"},
+            {"type": "function", "name": "transfer", "inputs": [
+                {"name": "to", "type": "address"},
+                {"name": "values", "type": "tuple[]", "components": [
+                    {"name": "id", "type": "uint256"},
+                    {"name": "data", "type": "bytes"}
+                ]}
+            ], "outputs": []}
+        ]"#;
+        let (signatures, selectors) = parse_functions(json);
+
+        let names: Vec<_> = signatures.iter().map(|sig| sig.ident.to_string()).collect();
+        assert_eq!(names, ["get_reserves", "transfer", "transfer_1"]);
+        assert_eq!(
+            selectors,
+            [
+                0x0902f1ac, // getReserves()
+                0xa9059cbb, // transfer(address,uint256)
+                0x7a94b6ab, // transfer(address,(uint256,bytes)[])
+            ]
+        );
+        assert!(matches!(
+            signatures[0].inputs.first(),
+            Some(syn::FnArg::Receiver(receiver)) if receiver.mutability.is_none()
+        ));
+        assert!(matches!(
+            signatures[1].inputs.first(),
+            Some(syn::FnArg::Receiver(receiver)) if receiver.mutability.is_some()
+        ));
+    }
+
+    #[test]
+    fn test_to_snake_case() {
+        assert_eq!(to_snake_case("balanceOf"), "balance_of");
+        assert_eq!(to_snake_case("DOMAIN_SEPARATOR"), "domain_separator");
+        assert_eq!(to_snake_case("WETH"), "weth");
+        assert_eq!(to_snake_case("tokenURI"), "token_uri");
+        assert_eq!(to_snake_case("token0"), "token0");
+    }
+}
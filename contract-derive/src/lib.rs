@@ -1,8 +1,13 @@
 extern crate proc_macro;
 use proc_macro::TokenStream;
 use quote::{format_ident, quote};
-use syn::{parse_macro_input, ImplItem, ItemImpl};
-use syn::{FnArg, ReturnType};
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, ImplItem, ItemImpl, ItemTrait, Lit, Meta, NestedMeta, TraitItem};
+use syn::{Attribute, FnArg, GenericArgument, PathArguments, ReturnType, Signature, Type};
+use syn::{Fields, Ident, ItemStruct, LitInt, LitStr, Token, Visibility};
+use tiny_keccak::{Hasher, Keccak};
+
+mod abi;
 
 #[proc_macro_attribute]
 pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
@@ -13,7 +18,7 @@ pub fn show_streams(attr: TokenStream, item: TokenStream) -> TokenStream {
 
 #[proc_macro_attribute]
 pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
-    let input = parse_macro_input!(item as ItemImpl);
+    let mut input = parse_macro_input!(item as ItemImpl);
     let struct_name = if let syn::Type::Path(type_path) = &*input.self_ty {
         &type_path.path.segments.first().unwrap().ident
     } else {
@@ -23,17 +28,28 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut public_methods = Vec::new();
 
     // Iterate over the items in the impl block to find pub methods
-    for item in input.items.iter() {
+    for item in input.items.iter_mut() {
         if let ImplItem::Method(method) = item {
             if let syn::Visibility::Public(_) = method.vis {
                 public_methods.push(method.clone());
             }
+            // `#[selector]` and `#[payable]` are only meaningful to this macro, so they must not
+            // reach the compiler.
+            method
+                .attrs
+                .retain(|attr| !attr.path.is_ident("selector") && !attr.path.is_ident("payable"));
         }
     }
 
-    let match_arms: Vec<_> = public_methods.iter().enumerate().map(|(index, method)| {
+    let selectors: Vec<u32> = public_methods
+        .iter()
+        .map(|method| method_selector(&method.sig, &method.attrs))
+        .collect();
+    let signatures: Vec<_> = public_methods.iter().map(|method| &method.sig).collect();
+    check_unique_selectors(&signatures, &selectors);
+
+    let match_arms: Vec<_> = public_methods.iter().zip(selectors).map(|(method, method_selector)| {
         let method_name = &method.sig.ident;
-        let method_selector = index as u32;
         let arg_types: Vec<_> = method.sig.inputs.iter().skip(1).map(|arg| {
             if let FnArg::Typed(pat_type) = arg {
                 let ty = &*pat_type.ty;
@@ -45,19 +61,35 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
         let arg_names: Vec<_> = (0..method.sig.inputs.len() - 1).map(|i| format_ident!("arg{}", i)).collect();
 
+        // `&mut self` methods may have changed `StorageCell`s, which must reach storage before
+        // returning.
+        let flush = match method.sig.inputs.first() {
+            Some(FnArg::Receiver(receiver)) if receiver.mutability.is_some() => quote! {
+                eth_riscv_runtime::types::flush_cells();
+            },
+            _ => quote! {},
+        };
+
         // Check if the method has a return type
         let return_handling = match &method.sig.output {
             ReturnType::Default => {
                 // No return value
                 quote! {
                     self.#method_name(#( #arg_names ),*);
+                    #flush
                 }
             }
             ReturnType::Type(_, return_type) => {
-                // Has return value
+                // Has return value. Tuples are encoded as multiple return values, like Solidity does.
+                let encode = if let Type::Tuple(_) = &**return_type {
+                    quote! { abi_encode_params }
+                } else {
+                    quote! { abi_encode }
+                };
                 quote! {
                     let result: #return_type = self.#method_name(#( #arg_names ),*);
-                    let result_bytes = result.abi_encode();
+                    #flush
+                    let result_bytes = result.#encode();
                     let result_size = result_bytes.len() as u64;
                     let result_ptr = result_bytes.as_ptr() as u64;
                     return_riscv(result_ptr, result_size);
@@ -65,9 +97,21 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         };
 
+        // Like in Solidity, methods that are not `#[payable]` reject calls that send value.
+        let value_check = if method.attrs.iter().any(|attr| attr.path.is_ident("payable")) {
+            quote! {}
+        } else {
+            quote! {
+                if !eth_riscv_runtime::msg_value().is_zero() {
+                    revert(&[]);
+                }
+            }
+        };
+
         quote! {
             #method_selector => {
-                let (#( #arg_names ),*) = <(#( #arg_types ),*)>::abi_decode(calldata, true).unwrap();
+                #value_check
+                let (#( #arg_names, )*) = <(#( #arg_types, )*)>::abi_decode_params(calldata, true).expect("invalid calldata");
                 #return_handling
             }
         }
@@ -76,10 +120,10 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
     // Generate the call method implementation
     let call_method = quote! {
         use alloy_sol_types::SolValue;
-        use eth_riscv_runtime::{revert, msg_sender, return_riscv, slice_from_raw_parts, Contract};
+        use eth_riscv_runtime::{revert, emit, msg_sender, return_riscv, slice_from_raw_parts, Contract};
 
         impl Contract for #struct_name {
-            fn call(&self) {
+            fn call(&mut self) {
                 let address: usize = 0x8000_0000;
                 let length = unsafe { slice_from_raw_parts(address, 8) };
                 let length = u64::from_le_bytes([length[0], length[1], length[2], length[3], length[4], length[5], length[6], length[7]]) as usize;
@@ -87,13 +131,16 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 self.call_with_data(calldata);
             }
 
-            fn call_with_data(&self, calldata: &[u8]) {
-                let selector = u32::from_le_bytes([calldata[0], calldata[1], calldata[2], calldata[3]]);
+            fn call_with_data(&mut self, calldata: &[u8]) {
+                if calldata.len() < 4 {
+                    revert(&[]);
+                }
+                let selector = u32::from_be_bytes([calldata[0], calldata[1], calldata[2], calldata[3]]);
                 let calldata = &calldata[4..];
 
                 match selector {
                     #( #match_arms )*
-                    _ => revert(),
+                    _ => revert(&[]),
                 }
 
                 return_riscv(0, 0);
@@ -103,7 +150,7 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
         #[eth_riscv_runtime::entry]
         fn main() -> !
         {
-            let contract = #struct_name::default();
+            let mut contract = #struct_name::default();
             contract.call();
             eth_riscv_runtime::return_riscv(0, 0)
         }
@@ -116,3 +163,600 @@ pub fn contract(_attr: TokenStream, item: TokenStream) -> TokenStream {
 
     TokenStream::from(output)
 }
+
+/// Lays out the fields of a contract struct in storage, following Solidity's rules.
+///
+/// Fields are placed in declaration order starting at slot 0, and fields smaller than a slot are
+/// packed together, according to their `StorageLayout::SIZE`. Each field is created with
+/// `StorageLayout::allocate` in a generated `impl Default`. A field can be moved to another slot
+/// with `#[slot(n)]`, and the fields after it continue from there. Overlapping fields are rejected
+/// at compile time.
+#[proc_macro_attribute]
+pub fn storage(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(item as ItemStruct);
+    let Fields::Named(fields) = &mut input.fields else {
+        panic!("Expected a struct with named fields.");
+    };
+
+    let mut layout_fields = Vec::new();
+    let mut initializers = Vec::new();
+    for (i, field) in fields.named.iter_mut().enumerate() {
+        let mut pinned_slot = quote! { core::option::Option::None };
+        for attr in field.attrs.iter().filter(|attr| attr.path.is_ident("slot")) {
+            let slot: u64 = attr
+                .parse_args::<LitInt>()
+                .and_then(|lit| lit.base10_parse())
+                .expect("Expected `#[slot(n)]` with an integer slot.");
+            pinned_slot = quote! { core::option::Option::Some(#slot) };
+        }
+        // `#[slot]` is only meaningful to this macro, so it must not reach the compiler.
+        field.attrs.retain(|attr| !attr.path.is_ident("slot"));
+
+        let name = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        layout_fields.push(quote! {
+            (<#ty as eth_riscv_runtime::types::StorageLayout>::SIZE, #pinned_slot)
+        });
+        initializers.push(quote! {
+            #name: eth_riscv_runtime::types::StorageLayout::allocate(
+                alloy_core::primitives::U256::from(LAYOUT[#i].0),
+                LAYOUT[#i].1,
+            )
+        });
+    }
+
+    let struct_name = &input.ident;
+    let field_count = initializers.len();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let output = quote! {
+        #input
+
+        impl #impl_generics core::default::Default for #struct_name #ty_generics #where_clause {
+            fn default() -> Self {
+                // Computed at compile time, so that overlapping fields fail to compile.
+                const LAYOUT: [(u64, u8); #field_count] =
+                    eth_riscv_runtime::types::layout([#( #layout_fields ),*]);
+                Self {
+                    #( #initializers, )*
+                }
+            }
+        }
+    };
+
+    TokenStream::from(output)
+}
+
+/// Implements `StorageEncode` and `StorageDecode` for a struct, so that it can be stored as a
+/// whole, e.g. as the value of a `Mapping` or a `TransientMapping`.
+///
+/// The fields are laid out like the fields of a Solidity struct: in declaration order, packed
+/// according to their `StorageEncode::SIZE`. The struct always starts at offset 0 and takes whole
+/// slots.
+#[proc_macro_derive(StorageCodec)]
+pub fn storage_codec(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemStruct);
+    TokenStream::from(storage_codec_impls(&input))
+}
+
+fn storage_codec_impls(input: &ItemStruct) -> proc_macro2::TokenStream {
+    let Fields::Named(fields) = &input.fields else {
+        panic!("Expected a struct with named fields.");
+    };
+
+    let names: Vec<_> = fields.named.iter().map(|field| &field.ident).collect();
+    let types: Vec<_> = fields.named.iter().map(|field| &field.ty).collect();
+    let indices: Vec<_> = (0..names.len()).collect();
+    let field_count = names.len();
+
+    let struct_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let layout = quote! {
+        const LAYOUT: [(u64, u8); #field_count] = eth_riscv_runtime::types::layout([
+            #( (<#types as eth_riscv_runtime::types::StorageEncode>::SIZE, core::option::Option::None) ),*
+        ]);
+    };
+    quote! {
+        impl #impl_generics eth_riscv_runtime::types::StorageEncode for #struct_name #ty_generics #where_clause {
+            const SIZE: usize = eth_riscv_runtime::types::layout_size([
+                #( <#types as eth_riscv_runtime::types::StorageEncode>::SIZE ),*
+            ]);
+
+            fn encode<S: eth_riscv_runtime::types::StorageArea>(&self, slot: alloy_core::primitives::U256, _offset: u8) {
+                #layout
+                #(
+                    eth_riscv_runtime::types::StorageEncode::encode::<S>(
+                        &self.#names,
+                        slot + alloy_core::primitives::U256::from(LAYOUT[#indices].0),
+                        LAYOUT[#indices].1,
+                    );
+                )*
+            }
+        }
+
+        impl #impl_generics eth_riscv_runtime::types::StorageDecode for #struct_name #ty_generics #where_clause {
+            fn decode<S: eth_riscv_runtime::types::StorageArea>(slot: alloy_core::primitives::U256, _offset: u8) -> Self {
+                #layout
+                Self {
+                    #(
+                        #names: eth_riscv_runtime::types::StorageDecode::decode::<S>(
+                            slot + alloy_core::primitives::U256::from(LAYOUT[#indices].0),
+                            LAYOUT[#indices].1,
+                        ),
+                    )*
+                }
+            }
+        }
+    }
+}
+
+/// Turns a trait into a typed client for the contracts implementing it.
+///
+/// For a trait `IERC20`, this generates a struct `IERC20` whose `new(address)` constructor binds it
+/// to a deployed contract. Each trait method becomes a method that ABI-encodes its arguments, calls
+/// the contract and decodes the returned value, using the same selectors as `#[contract]`. Methods
+/// taking `&self` are view functions, called with `static_call`, while methods taking `&mut self`
+/// are called with `call`. Failed calls return an `eth_riscv_runtime::CallError`.
+#[proc_macro_attribute]
+pub fn interface(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemTrait);
+    let methods: Vec<_> = input
+        .items
+        .iter()
+        .map(|item| match item {
+            TraitItem::Method(method) => method,
+            _ => panic!("Interfaces can only contain methods."),
+        })
+        .collect();
+
+    let selectors: Vec<u32> = methods
+        .iter()
+        .map(|method| method_selector(&method.sig, &method.attrs))
+        .collect();
+    let signatures: Vec<_> = methods.iter().map(|method| &method.sig).collect();
+    check_unique_selectors(&signatures, &selectors);
+
+    let client_methods: Vec<_> = signatures
+        .iter()
+        .zip(selectors)
+        .map(|(sig, selector)| client_method(sig, selector))
+        .collect();
+
+    TokenStream::from(client(&input.vis, &input.ident, &client_methods))
+}
+
+/// Generates typed client bindings from a Solidity ABI JSON file, like `#[interface]` does from a
+/// trait.
+///
+/// `abi_json!(pub IUniswapV2Pair, "abi/IUniswapV2Pair.json")` generates a struct
+/// `IUniswapV2Pair` with a snake_case method for each function of the ABI, e.g. `get_reserves`
+/// for `getReserves`. `view` and `pure` functions are called with `static_call`, and the others
+/// with `call`. Overloaded functions get a numeric suffix, e.g. `safe_transfer_from_1`.
+///
+/// The path is relative to the crate's manifest directory. The file may contain either the ABI
+/// array or a build artifact with an `abi` field. Contracts using dynamic types such as `string`
+/// or `T[]` need `extern crate alloc;`.
+#[proc_macro]
+pub fn abi_json(input: TokenStream) -> TokenStream {
+    let AbiJsonInput { vis, name, path } = parse_macro_input!(input as AbiJsonInput);
+    let full_path = std::path::Path::new(
+        &std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set"),
+    )
+    .join(path.value());
+    let json = std::fs::read_to_string(&full_path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", full_path.display(), e));
+
+    let (signatures, selectors) = abi::parse_functions(&json);
+    let signatures_ref: Vec<_> = signatures.iter().collect();
+    check_unique_selectors(&signatures_ref, &selectors);
+
+    let client_methods: Vec<_> = signatures
+        .iter()
+        .zip(selectors)
+        .map(|(sig, selector)| client_method(sig, selector))
+        .collect();
+    let client = client(&vis, &name, &client_methods);
+
+    // Rebuild the bindings whenever the ABI file changes.
+    let full_path = full_path.display().to_string();
+    let output = quote! {
+        const _: &[u8] = include_bytes!(#full_path);
+        #client
+    };
+
+    TokenStream::from(output)
+}
+
+struct AbiJsonInput {
+    vis: Visibility,
+    name: Ident,
+    path: LitStr,
+}
+
+impl Parse for AbiJsonInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let vis = input.parse()?;
+        let name = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let path = input.parse()?;
+        Ok(AbiJsonInput { vis, name, path })
+    }
+}
+
+/// Generates the client struct `name`, bound to a contract address, with `methods`.
+fn client(
+    vis: &Visibility,
+    name: &Ident,
+    methods: &[proc_macro2::TokenStream],
+) -> proc_macro2::TokenStream {
+    quote! {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        #vis struct #name {
+            address: alloy_core::primitives::Address,
+        }
+
+        // Bindings usually cover more functions than a contract calls.
+        #[allow(dead_code)]
+        impl #name {
+            pub fn new(address: alloy_core::primitives::Address) -> Self {
+                Self { address }
+            }
+
+            pub fn address(&self) -> alloy_core::primitives::Address {
+                self.address
+            }
+
+            #( #methods )*
+        }
+    }
+}
+
+/// Generates the client method calling the contract method with signature `sig`.
+fn client_method(sig: &Signature, selector: u32) -> proc_macro2::TokenStream {
+    let method_name = &sig.ident;
+    let is_mut = match sig.inputs.first() {
+        Some(FnArg::Receiver(receiver)) => receiver.mutability.is_some(),
+        _ => panic!("Interface methods must take `&self` or `&mut self`."),
+    };
+    let (arg_names, arg_types): (Vec<_>, Vec<_>) = sig
+        .inputs
+        .iter()
+        .skip(1)
+        .enumerate()
+        .map(|(i, arg)| match arg {
+            FnArg::Typed(pat_type) => (format_ident!("arg{}", i), &*pat_type.ty),
+            FnArg::Receiver(_) => panic!("Expected typed arguments"),
+        })
+        .unzip();
+
+    let call = if is_mut {
+        quote! {
+            eth_riscv_runtime::call(
+                u64::MAX,
+                self.address.as_ptr() as u64,
+                alloy_core::primitives::U256::ZERO,
+                calldata.as_ptr() as u64,
+                calldata.len() as u64,
+                0,
+                0,
+            )
+        }
+    } else {
+        quote! {
+            eth_riscv_runtime::static_call(
+                u64::MAX,
+                self.address.as_ptr() as u64,
+                calldata.as_ptr() as u64,
+                calldata.len() as u64,
+                0,
+                0,
+            )
+        }
+    };
+
+    // Tuples are decoded as multiple return values, like the dispatcher of `#[contract]` encodes
+    // them.
+    let (return_type, decode) = match &sig.output {
+        ReturnType::Default => (quote! { () }, quote! { Ok(()) }),
+        ReturnType::Type(_, return_type) => {
+            let decode = if let Type::Tuple(_) = &**return_type {
+                quote! { abi_decode_params }
+            } else {
+                quote! { abi_decode }
+            };
+            (
+                quote! { #return_type },
+                quote! {
+                    <#return_type as alloy_sol_types::SolValue>::#decode(&eth_riscv_runtime::return_data(), true)
+                        .map_err(|_| eth_riscv_runtime::CallError::InvalidReturnData)
+                },
+            )
+        }
+    };
+
+    let receiver = if is_mut {
+        quote! { &mut self }
+    } else {
+        quote! { &self }
+    };
+
+    quote! {
+        pub fn #method_name(#receiver, #( #arg_names: #arg_types ),*) -> Result<#return_type, eth_riscv_runtime::CallError> {
+            use alloy_sol_types::SolValue;
+            let mut calldata = #selector.to_be_bytes().to_vec();
+            calldata.extend_from_slice(&(#( #arg_names, )*).abi_encode_params());
+            match #call {
+                Ok(_) => #decode,
+                Err(_) => Err(eth_riscv_runtime::CallError::Revert(eth_riscv_runtime::return_data())),
+            }
+        }
+    }
+}
+
+/// Panics if two methods have the same selector, since only one of them could ever be called.
+fn check_unique_selectors(signatures: &[&Signature], selectors: &[u32]) {
+    for (i, selector) in selectors.iter().enumerate() {
+        if let Some(j) = selectors[..i].iter().position(|other| other == selector) {
+            panic!(
+                "Methods `{}` and `{}` have the same selector 0x{:08x}.",
+                signatures[j].ident, signatures[i].ident, selector
+            );
+        }
+    }
+}
+
+/// Computes the Solidity function selector of a contract method: the first four bytes of the
+/// keccak256 hash of its signature, e.g. `balanceOf(address)` for `fn balance_of(&self, owner: Address)`.
+///
+/// The method name can be overridden with `#[selector(name = "transferFrom")]`, and the whole
+/// selector with `#[selector(id = 0x23b872dd)]`.
+fn method_selector(sig: &Signature, attrs: &[Attribute]) -> u32 {
+    let mut name = to_camel_case(&sig.ident.to_string());
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("selector")) {
+        let Ok(Meta::List(list)) = attr.parse_meta() else {
+            panic!("Expected `#[selector(name = \"...\")]` or `#[selector(id = ...)]`.");
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => {
+                    match &nv.lit {
+                        Lit::Str(lit) => name = lit.value(),
+                        _ => panic!("Expected a string literal for the selector name."),
+                    }
+                }
+                NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("id") => match &nv.lit {
+                    Lit::Int(lit) => {
+                        return lit
+                            .base10_parse()
+                            .expect("Selector id must fit in 4 bytes.")
+                    }
+                    _ => panic!("Expected an integer literal for the selector id."),
+                },
+                _ => panic!("Unknown selector option, expected `name` or `id`."),
+            }
+        }
+    }
+
+    let arg_types: Vec<_> = sig
+        .inputs
+        .iter()
+        .skip(1)
+        .map(|arg| match arg {
+            FnArg::Typed(pat_type) => sol_type(&pat_type.ty),
+            FnArg::Receiver(_) => panic!("Expected typed arguments"),
+        })
+        .collect();
+
+    selector(&format!("{}({})", name, arg_types.join(",")))
+}
+
+/// Returns the first four bytes of the keccak256 hash of `signature` as a big-endian `u32`.
+pub(crate) fn selector(signature: &str) -> u32 {
+    let mut output = [0u8; 32];
+    let mut hasher = Keccak::v256();
+    hasher.update(signature.as_bytes());
+    hasher.finalize(&mut output);
+    u32::from_be_bytes([output[0], output[1], output[2], output[3]])
+}
+
+/// Converts a Rust method name to the Solidity naming convention, e.g. `balance_of` to `balanceOf`.
+fn to_camel_case(name: &str) -> String {
+    let mut parts = name.split('_').filter(|part| !part.is_empty());
+    let mut result = parts.next().unwrap_or_default().to_string();
+    for part in parts {
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            result.extend(first.to_uppercase());
+            result.push_str(chars.as_str());
+        }
+    }
+    result
+}
+
+/// Maps a Rust argument type to the name of the Solidity type it is ABI-encoded as.
+fn sol_type(ty: &Type) -> String {
+    match ty {
+        Type::Array(array) => {
+            let len = &array.len;
+            format!("{}[{}]", sol_type(&array.elem), quote! { #len })
+        }
+        Type::Tuple(tuple) => {
+            let elems: Vec<_> = tuple.elems.iter().map(sol_type).collect();
+            format!("({})", elems.join(","))
+        }
+        Type::Paren(paren) => sol_type(&paren.elem),
+        Type::Path(type_path) => {
+            let segment = type_path.path.segments.last().unwrap();
+            let ident = segment.ident.to_string();
+            let const_args: Vec<_> = match &segment.arguments {
+                PathArguments::AngleBracketed(args) => args
+                    .args
+                    .iter()
+                    .map(|arg| quote! { #arg }.to_string())
+                    .collect(),
+                _ => Vec::new(),
+            };
+            match ident.as_str() {
+                "Address" => "address".to_string(),
+                "bool" => "bool".to_string(),
+                "String" => "string".to_string(),
+                "Bytes" => "bytes".to_string(),
+                "u8" | "u16" | "u32" | "u64" | "u128" => format!("uint{}", &ident[1..]),
+                "i8" | "i16" | "i32" | "i64" | "i128" => format!("int{}", &ident[1..]),
+                "Uint" => format!("uint{}", const_args[0]),
+                "Signed" => format!("int{}", const_args[0]),
+                "FixedBytes" => format!("bytes{}", const_args[0]),
+                "Vec" => match &segment.arguments {
+                    PathArguments::AngleBracketed(args) => match args.args.first() {
+                        Some(GenericArgument::Type(elem)) => format!("{}[]", sol_type(elem)),
+                        _ => panic!("Expected `Vec<T>`."),
+                    },
+                    _ => panic!("Expected `Vec<T>`."),
+                },
+                // Aliases such as `U256`, `I128` and `B256`.
+                _ if ident.len() > 1 && ident[1..].chars().all(|c| c.is_ascii_digit()) => {
+                    let bits: usize = ident[1..].parse().unwrap();
+                    match &ident[..1] {
+                        "U" => format!("uint{}", bits),
+                        "I" => format!("int{}", bits),
+                        "B" => format!("bytes{}", bits / 8),
+                        _ => panic!("Unsupported argument type `{}`.", ident),
+                    }
+                }
+                _ => panic!("Unsupported argument type `{}`.", ident),
+            }
+        }
+        _ => panic!("Unsupported argument type `{}`.", quote! { #ty }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_selector() {
+        let methods: [(syn::ImplItemMethod, u32); 5] = [
+            // transfer(address,uint256)
+            (
+                syn::parse_quote! {
+                    pub fn transfer(&mut self, to: Address, value: U256) -> bool {}
+                },
+                0xa9059cbb,
+            ),
+            // balanceOf(address)
+            (
+                syn::parse_quote! {
+                    pub fn balance_of(&self, owner: Address) -> U256 {}
+                },
+                0x70a08231,
+            ),
+            // transferFrom(address,address,uint256)
+            (
+                syn::parse_quote! {
+                    #[selector(name = "transferFrom")]
+                    pub fn transfer_tokens(&mut self, from: Address, to: Address, value: U256) {}
+                },
+                0x23b872dd,
+            ),
+            (
+                syn::parse_quote! {
+                    #[selector(id = 0x12345678)]
+                    pub fn anything(&self, value: U256) {}
+                },
+                0x12345678,
+            ),
+            // aggregate((address,bytes)[])
+            (
+                syn::parse_quote! {
+                    pub fn aggregate(&mut self, calls: Vec<(Address, Bytes)>) {}
+                },
+                0x252dba42,
+            ),
+        ];
+        for (method, expected) in methods {
+            assert_eq!(method_selector(&method.sig, &method.attrs), expected);
+        }
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("balance_of"), "balanceOf");
+        assert_eq!(to_camel_case("x_balance_of_via"), "xBalanceOfVia");
+        assert_eq!(to_camel_case("transfer"), "transfer");
+        assert_eq!(to_camel_case("_private"), "private");
+    }
+
+    #[test]
+    fn test_sol_type() {
+        let types: [(Type, &str); 15] = [
+            (syn::parse_quote!(Address), "address"),
+            (syn::parse_quote!(bool), "bool"),
+            (syn::parse_quote!(String), "string"),
+            (syn::parse_quote!(Bytes), "bytes"),
+            (syn::parse_quote!(u64), "uint64"),
+            (syn::parse_quote!(i8), "int8"),
+            (syn::parse_quote!(U256), "uint256"),
+            (syn::parse_quote!(I128), "int128"),
+            (syn::parse_quote!(B256), "bytes32"),
+            (syn::parse_quote!(Uint<24, 1>), "uint24"),
+            (syn::parse_quote!(FixedBytes<4>), "bytes4"),
+            (syn::parse_quote!(Vec<Address>), "address[]"),
+            (syn::parse_quote!([U256; 3]), "uint256[3]"),
+            (syn::parse_quote!((Address, U256)), "(address,uint256)"),
+            (syn::parse_quote!(Vec<(U256, Bytes)>), "(uint256,bytes)[]"),
+        ];
+        for (ty, expected) in types {
+            assert_eq!(sol_type(&ty), expected);
+        }
+    }
+
+    #[test]
+    fn test_storage_codec_layout() {
+        let input: ItemStruct = syn::parse_quote! {
+            struct Position {
+                amount: U256,
+                debt: U256,
+                last_update: u64,
+            }
+        };
+        let output = storage_codec_impls(&input).to_string();
+
+        // The struct takes as many slots as its fields need, laid out in declaration order.
+        let size = quote! {
+            const SIZE: usize = eth_riscv_runtime::types::layout_size([
+                <U256 as eth_riscv_runtime::types::StorageEncode>::SIZE,
+                <U256 as eth_riscv_runtime::types::StorageEncode>::SIZE,
+                <u64 as eth_riscv_runtime::types::StorageEncode>::SIZE
+            ]);
+        };
+        assert!(output.contains(&size.to_string()));
+        let layout = quote! {
+            const LAYOUT: [(u64, u8); 3usize] = eth_riscv_runtime::types::layout([
+                (<U256 as eth_riscv_runtime::types::StorageEncode>::SIZE, core::option::Option::None),
+                (<U256 as eth_riscv_runtime::types::StorageEncode>::SIZE, core::option::Option::None),
+                (<u64 as eth_riscv_runtime::types::StorageEncode>::SIZE, core::option::Option::None)
+            ]);
+        };
+        assert_eq!(output.matches(&layout.to_string()).count(), 2);
+
+        // Each field is written and read at its own slot and offset from the struct's slot.
+        for (i, name) in ["amount", "debt", "last_update"].iter().enumerate() {
+            let name = format_ident!("{}", name);
+            let encode = quote! {
+                eth_riscv_runtime::types::StorageEncode::encode::<S>(
+                    &self.#name,
+                    slot + alloy_core::primitives::U256::from(LAYOUT[#i].0),
+                    LAYOUT[#i].1,
+                );
+            };
+            assert!(output.contains(&encode.to_string()));
+            let decode = quote! {
+                #name: eth_riscv_runtime::types::StorageDecode::decode::<S>(
+                    slot + alloy_core::primitives::U256::from(LAYOUT[#i].0),
+                    LAYOUT[#i].1,
+                ),
+            };
+            assert!(output.contains(&decode.to_string()));
+        }
+    }
+}
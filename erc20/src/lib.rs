@@ -3,41 +3,86 @@
 
 use core::default::Default;
 
-use contract_derive::contract;
-use eth_riscv_runtime::types::Mapping;
+use contract_derive::{contract, storage};
+use eth_riscv_runtime::checked_add;
+use eth_riscv_runtime::types::{Mapping, StorageCell};
 
-use alloy_core::primitives::{Address, address};
+use alloy_core::primitives::{Address, address, U256};
+use alloy_sol_types::{sol, SolError};
 
-#[derive(Default)]
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+
+    error InsufficientBalance(address from, uint256 balance, uint256 needed);
+    error Unauthorized(address caller);
+}
+
+#[storage]
 pub struct ERC20 {
-    balance: Mapping<Address, u64>,
+    balance: Mapping<Address, U256>,
+    allowance: Mapping<Address, Mapping<Address, U256>>,
+    total_supply: StorageCell<U256>,
 }
 
 #[contract]
 impl ERC20 {
-    pub fn balance_of(&self, owner: Address) -> u64 {
+    pub fn balance_of(&self, owner: Address) -> U256 {
         self.balance.read(owner)
     }
 
-    pub fn transfer(&self, from: Address, to: Address, value: u64) {
+    pub fn allowance(&self, owner: Address, spender: Address) -> U256 {
+        self.allowance.at(owner).read(spender)
+    }
+
+    pub fn total_supply(&self) -> U256 {
+        *self.total_supply
+    }
+
+    pub fn approve(&mut self, spender: Address, value: U256) -> bool {
+        let owner = msg_sender();
+        self.allowance.at(owner).write(spender, value);
+
+        emit(Approval {
+            owner,
+            spender,
+            value,
+        });
+        true
+    }
+
+    pub fn transfer(&mut self, from: Address, to: Address, value: U256) {
         let from_balance = self.balance.read(from);
         let to_balance = self.balance.read(to);
 
-        if from == to || from_balance < value {
-            revert();
+        if from == to {
+            revert(&[]);
+        }
+        if from_balance < value {
+            let error = InsufficientBalance {
+                from,
+                balance: from_balance,
+                needed: value,
+            };
+            revert(&error.abi_encode());
         }
 
         self.balance.write(from, from_balance - value);
-        self.balance.write(to, to_balance + value);
+        self.balance.write(to, checked_add(to_balance, value));
+
+        emit(Transfer { from, to, value });
     }
 
-    pub fn mint(&self, to: Address, value: u64) {
+    pub fn mint(&mut self, to: Address, value: U256) {
         let owner = msg_sender();
         if owner != address!("0000000000000000000000000000000000000007") {
-            revert();
+            revert(&Unauthorized { caller: owner }.abi_encode());
         }
 
         let to_balance = self.balance.read(to);
-        self.balance.write(to, to_balance + value);
+        self.balance.write(to, checked_add(to_balance, value));
+        *self.total_supply = checked_add(*self.total_supply, value);
+
+        emit(Transfer { from: Address::ZERO, to, value });
     }
 }
@@ -0,0 +1,65 @@
+#![no_std]
+#![no_main]
+
+use core::default::Default;
+
+use contract_derive::{abi_json, contract, interface};
+use eth_riscv_runtime::{call, static_call, CallError};
+
+use alloy_core::primitives::{Address, Bytes, U256};
+
+abi_json!(IERC20, "abi/IERC20.json");
+
+#[interface]
+trait IERC20x {
+    fn x_balance_of(&self, owner: Address, token: Address) -> U256;
+}
+
+/// Queries balances of ERC20 tokens through cross-contract calls.
+#[derive(Default)]
+pub struct ERC20x;
+
+#[contract]
+impl ERC20x {
+    pub fn x_balance_of(&self, owner: Address, token: Address) -> U256 {
+        IERC20::new(token)
+            .balance_of(owner)
+            .unwrap_or_else(bubble_up)
+    }
+
+    pub fn x_balance_of_via(&self, owner: Address, token: Address, via: Address) -> U256 {
+        IERC20x::new(via)
+            .x_balance_of(owner, token)
+            .unwrap_or_else(bubble_up)
+    }
+
+    pub fn call_succeeds(&self, target: Address, calldata: Bytes) -> bool {
+        call(
+            u64::MAX,
+            target.as_ptr() as u64,
+            U256::ZERO,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        )
+        .is_ok()
+    }
+
+    pub fn static_call_succeeds(&self, target: Address, calldata: Bytes) -> bool {
+        static_call(
+            u64::MAX,
+            target.as_ptr() as u64,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        )
+        .is_ok()
+    }
+}
+
+/// Reverts with the callee's revert reason.
+fn bubble_up<T>(error: CallError) -> T {
+    revert(error.revert_data())
+}
@@ -0,0 +1,82 @@
+//! Gas cost of executing RISC-V instructions.
+//!
+//! Costs are expressed in EVM gas units. Syscalls (`ecall`) cost nothing here, since the host
+//! charges for them depending on the operation they perform.
+
+/// Integer arithmetic, logic, branches and jumps.
+pub const BASE: u64 = 1;
+/// Loads and stores.
+pub const MEMORY: u64 = 2;
+/// Multiplications.
+pub const MUL: u64 = 3;
+/// Divisions and remainders.
+pub const DIV: u64 = 5;
+/// Atomic memory operations.
+pub const ATOMIC: u64 = 5;
+/// Environment calls, charged by the host instead.
+pub const ECALL: u64 = 0;
+
+/// Returns the gas cost of the instruction `inst`, which may be compressed.
+pub fn instruction_cost(inst: u64) -> u64 {
+    if inst & 0b11 != 0b11 {
+        return compressed_instruction_cost(inst);
+    }
+
+    let opcode = inst & 0x7f;
+    let funct3 = (inst >> 12) & 0x7;
+    let funct7 = (inst >> 25) & 0x7f;
+    match opcode {
+        // LOAD, LOAD-FP, STORE, STORE-FP
+        0x03 | 0x07 | 0x23 | 0x27 => MEMORY,
+        // AMO
+        0x2f => ATOMIC,
+        // OP and OP-32 from the M extension
+        0x33 | 0x3b if funct7 == 0x01 => {
+            if funct3 < 4 {
+                MUL
+            } else {
+                DIV
+            }
+        }
+        // SYSTEM: ecall, ebreak and CSR accesses
+        0x73 if inst == 0x73 => ECALL,
+        _ => BASE,
+    }
+}
+
+fn compressed_instruction_cost(inst: u64) -> u64 {
+    let quadrant = inst & 0b11;
+    let funct3 = (inst >> 13) & 0x7;
+    match (quadrant, funct3) {
+        // C.ADDI4SPN
+        (0, 0) => BASE,
+        // Register-based loads and stores
+        (0, _) => MEMORY,
+        // Stack-pointer-based loads and stores
+        (2, 1..=3) | (2, 5..=7) => MEMORY,
+        _ => BASE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_cost() {
+        // addi a0, a0, 1
+        assert_eq!(instruction_cost(0x00150513), BASE);
+        // ld a1, 8(a0)
+        assert_eq!(instruction_cost(0x00853583), MEMORY);
+        // mul a0, a0, a1
+        assert_eq!(instruction_cost(0x02b50533), MUL);
+        // divu a0, a0, a1
+        assert_eq!(instruction_cost(0x02b55533), DIV);
+        // ecall
+        assert_eq!(instruction_cost(0x00000073), ECALL);
+        // c.li a0, 5
+        assert_eq!(instruction_cost(0x4515), BASE);
+        // c.ld a0, 8(a1)
+        assert_eq!(instruction_cost(0x6588), MEMORY);
+    }
+}
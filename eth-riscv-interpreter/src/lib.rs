@@ -1,4 +1,15 @@
-use rvemu::{bus::DRAM_BASE, dram::DRAM_SIZE, emulator::Emulator};
+use rvemu::{bus::DRAM_BASE, dram::DRAM_SIZE, emulator::Emulator, exception::Exception};
+
+pub mod gas;
+
+/// Why the emulator stopped running.
+#[derive(Debug, PartialEq)]
+pub enum Halt {
+    /// The guest raised an exception, e.g. an `ecall` to request a syscall.
+    Exception(Exception),
+    /// The gas limit was exceeded.
+    OutOfGas,
+}
 
 pub fn setup_from_elf(elf_data: &[u8], call_data: &[u8]) -> Emulator {
     let elf = goblin::elf::Elf::parse(elf_data)
@@ -25,6 +36,32 @@ pub fn setup_from_elf(elf_data: &[u8], call_data: &[u8]) -> Emulator {
     emu
 }
 
+/// Runs the emulator until the guest raises an exception or `gas_limit` is exceeded.
+///
+/// Returns why the emulator halted and the gas consumed by the executed instructions, which is
+/// the whole `gas_limit` when running out of gas. After an `ecall` the program counter points to
+/// the next instruction, so the emulator can be run again once the syscall has been handled.
+pub fn run(emu: &mut Emulator, gas_limit: u64) -> (Halt, u64) {
+    let mut gas_used = 0;
+    loop {
+        match emu.cpu.execute() {
+            Ok(inst) => {
+                gas_used += gas::instruction_cost(inst);
+                if gas_used > gas_limit {
+                    return (Halt::OutOfGas, gas_limit);
+                }
+            }
+            Err(exception) => {
+                if exception == Exception::EnvironmentCallFromMMode {
+                    // `ecall` is never compressed, and is not retired by `execute`.
+                    emu.cpu.pc += 4;
+                }
+                return (Halt::Exception(exception), gas_used);
+            }
+        }
+    }
+}
+
 fn load_sections(mem: &mut Vec<u8>, elf: &goblin::elf::Elf, elf_data: &[u8]) {
     for ph in &elf.program_headers {
         if ph.p_type == goblin::elf::program_header::PT_LOAD {
@@ -72,4 +109,20 @@ mod tests {
         let data = u64::from_le_bytes(data_bytes.try_into().unwrap());
         assert_eq!(data, 5);
     }
+
+    #[test]
+    fn test_run_with_gas() {
+        let elf_data = fs::read("../asm-runtime-example/runtime").unwrap();
+
+        let mut emu = setup_from_elf(&elf_data, &[]);
+        let (halt, gas_used) = run(&mut emu, 1000);
+        assert_eq!(halt, Halt::Exception(Exception::EnvironmentCallFromMMode));
+        assert!(gas_used > 0);
+
+        // The same program runs out of gas with one unit less.
+        let mut emu = setup_from_elf(&elf_data, &[]);
+        let (halt, gas_used_oog) = run(&mut emu, gas_used - 1);
+        assert_eq!(halt, Halt::OutOfGas);
+        assert_eq!(gas_used_oog, gas_used - 1);
+    }
 }
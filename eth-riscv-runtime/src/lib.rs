@@ -1,19 +1,25 @@
 #![no_std]
 #![no_main]
-#![feature(alloc_error_handler, maybe_uninit_write_slice, round_char_boundary)]
+#![feature(alloc_error_handler, maybe_uninit_write_slice, round_char_boundary, panic_info_message)]
 
 use core::arch::asm;
+use core::fmt::Write;
 use core::panic::PanicInfo;
 use core::slice;
 pub use riscv_rt::entry;
-use alloy_core::primitives::Address;
+use alloy_core::primitives::{Address, B256, U256};
+use alloy_sol_types::{Panic, PanicKind, Revert, SolError, SolEvent};
 
-mod alloc;
+extern crate alloc;
+use alloc::{string::String, vec::Vec};
+
+mod allocator;
+pub mod precompiles;
 pub mod types;
 
 pub trait Contract {
-    fn call(&self);
-    fn call_with_data(&self, calldata: &[u8]);
+    fn call(&mut self);
+    fn call_with_data(&mut self, calldata: &[u8]);
 }
 
 pub unsafe fn slice_from_raw_parts(address: usize, length: usize) -> &'static [u8] {
@@ -21,22 +27,63 @@ pub unsafe fn slice_from_raw_parts(address: usize, length: usize) -> &'static [u
 }
 
 #[panic_handler]
-unsafe fn panic(_panic: &PanicInfo<'_>) -> ! {
+unsafe fn panic(panic: &PanicInfo<'_>) -> ! {
     static mut IS_PANICKING: bool = false;
 
     if !IS_PANICKING {
         IS_PANICKING = true;
 
-        revert();
-        // TODO with string
-        //print!("{panic}\n");
+        let mut message = String::new();
+        if let Some(args) = panic.message() {
+            let _ = write!(message, "{}", args);
+        }
+        revert(&panic_revert_data(&message));
+    } else {
+        // Formatting the panic message panicked, so there is nothing left to report.
+        revert(&[]);
+    }
+}
+
+/// Encodes a panic message as revert data, like Solidity does: failures Solidity reports with a
+/// panic code, such as arithmetic overflows, become `Panic(uint256)`, and any other message
+/// becomes `Error(string)`.
+fn panic_revert_data(message: &str) -> Vec<u8> {
+    let kind = if message.starts_with("attempt to") && message.ends_with("with overflow") {
+        Some(PanicKind::UnderOverflow)
+    } else if message.starts_with("attempt to divide by zero")
+        || message.starts_with("attempt to calculate the remainder with a divisor of zero")
+    {
+        Some(PanicKind::DivisionByZero)
+    } else if message.starts_with("index out of bounds") {
+        Some(PanicKind::ArrayOutOfBounds)
+    } else if message.starts_with("assertion") {
+        Some(PanicKind::Assert)
     } else {
-        revert();
-        // TODO with string
-        //print_str("Panic handler has panicked! Things are very dire indeed...\n");
+        None
+    };
+
+    match kind {
+        Some(kind) => Panic::from(kind).abi_encode(),
+        None => Revert::from(message).abi_encode(),
     }
 }
 
+/// Returns `a + b`, reverting with `Panic(0x11)` if it overflows, like Solidity's checked
+/// arithmetic. `U256` operators wrap around instead, even with `overflow-checks`.
+pub fn checked_add(a: U256, b: U256) -> U256 {
+    a.checked_add(b).unwrap_or_else(overflow)
+}
+
+/// Returns `a - b`, reverting with `Panic(0x11)` if it underflows, like Solidity's checked
+/// arithmetic.
+pub fn checked_sub(a: U256, b: U256) -> U256 {
+    a.checked_sub(b).unwrap_or_else(overflow)
+}
+
+fn overflow() -> U256 {
+    revert(&Panic::from(PanicKind::UnderOverflow).abi_encode())
+}
+
 use eth_riscv_syscalls::Syscall;
 
 pub fn return_riscv(addr: u64, offset: u64) -> ! {
@@ -46,33 +93,287 @@ pub fn return_riscv(addr: u64, offset: u64) -> ! {
     unreachable!()
 }
 
-pub fn sload(key: u64) -> u64 {
-    let value: u64;
+pub fn sload(key: U256) -> U256 {
+    let key = key.to_be_bytes::<32>();
+    let mut value = [0u8; 32];
     unsafe {
-        asm!("ecall", lateout("a0") value, in("a0") key, in("t0") u32::from(Syscall::SLoad));
+        asm!("ecall", in("a0") key.as_ptr() as u64, in("a1") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::SLoad));
     }
-    value
+    U256::from_be_bytes(value)
+}
+
+pub fn sstore(key: U256, value: U256) {
+    let key = key.to_be_bytes::<32>();
+    let value = value.to_be_bytes::<32>();
+    unsafe {
+        asm!("ecall", in("a0") key.as_ptr() as u64, in("a1") value.as_ptr() as u64, in("t0") u32::from(Syscall::SStore));
+    }
+}
+
+/// Reads `key` from the transient storage, which is cleared at the end of the transaction.
+pub fn tload(key: U256) -> U256 {
+    let key = key.to_be_bytes::<32>();
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") key.as_ptr() as u64, in("a1") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::TLoad));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Writes `value` to `key` in the transient storage, which is cleared at the end of the
+/// transaction.
+pub fn tstore(key: U256, value: U256) {
+    let key = key.to_be_bytes::<32>();
+    let value = value.to_be_bytes::<32>();
+    unsafe {
+        asm!("ecall", in("a0") key.as_ptr() as u64, in("a1") value.as_ptr() as u64, in("t0") u32::from(Syscall::TStore));
+    }
+}
+
+/// The reason a typed call to another contract, e.g. through an `#[interface]`, failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallError {
+    /// The callee reverted with this data.
+    Revert(Vec<u8>),
+    /// The callee returned data that could not be decoded as the expected type.
+    InvalidReturnData,
+}
+
+impl CallError {
+    /// Returns the data the callee reverted with, which is empty for invalid return data.
+    pub fn revert_data(&self) -> &[u8] {
+        match self {
+            CallError::Revert(data) => data,
+            CallError::InvalidReturnData => &[],
+        }
+    }
+}
+
+/// Calls the contract at address `addr` with at most `gas`, copying up to `out_size` bytes of the
+/// data it returns to `out_mem`. Like in the EVM, the gas given to the callee is capped to all but
+/// one 64th of the remaining gas, so `u64::MAX` forwards as much gas as possible.
+///
+/// Returns `Ok` with the size of the returned data if the call succeeded, and `Err` with the size
+/// of the revert data otherwise. The whole data can be read with [`return_data`].
+///
+/// The changed `StorageCell`s are written to storage before the call, and loaded again after it.
+pub fn call(
+    gas: u64,
+    addr: u64,
+    value: U256,
+    in_mem: u64,
+    in_size: u64,
+    out_mem: u64,
+    out_size: u64,
+) -> Result<u64, u64> {
+    types::flush_cells();
+    let value = value.to_be_bytes::<32>();
+    let success: u64;
+    let return_data_size: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") addr => success, inlateout("a1") value.as_ptr() as u64 => return_data_size, in("a2") in_mem, in("a3") in_size, in("a4") out_mem, in("a5") out_size, in("a6") gas, in("t0") u32::from(Syscall::Call));
+    }
+    types::clear_cells();
+    if success != 0 {
+        Ok(return_data_size)
+    } else {
+        Err(return_data_size)
+    }
+}
+
+/// Runs the code of the contract at address `addr` on this contract's storage and balance, keeping
+/// the current caller and value. See [`call`] for the arguments and the returned value.
+pub fn delegate_call(
+    gas: u64,
+    addr: u64,
+    in_mem: u64,
+    in_size: u64,
+    out_mem: u64,
+    out_size: u64,
+) -> Result<u64, u64> {
+    types::flush_cells();
+    let success: u64;
+    let return_data_size: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") addr => success, inlateout("a1") in_mem => return_data_size, in("a2") in_size, in("a3") out_mem, in("a4") out_size, in("a5") gas, in("t0") u32::from(Syscall::DelegateCall));
+    }
+    types::clear_cells();
+    if success != 0 {
+        Ok(return_data_size)
+    } else {
+        Err(return_data_size)
+    }
+}
+
+/// Calls the contract at address `addr` without allowing it, or any contract it calls, to modify
+/// the state. See [`call`] for the arguments and the returned value.
+pub fn static_call(
+    gas: u64,
+    addr: u64,
+    in_mem: u64,
+    in_size: u64,
+    out_mem: u64,
+    out_size: u64,
+) -> Result<u64, u64> {
+    types::flush_cells();
+    let success: u64;
+    let return_data_size: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") addr => success, inlateout("a1") in_mem => return_data_size, in("a2") in_size, in("a3") out_mem, in("a4") out_size, in("a5") gas, in("t0") u32::from(Syscall::StaticCall));
+    }
+    if success != 0 {
+        Ok(return_data_size)
+    } else {
+        Err(return_data_size)
+    }
+}
+
+/// Runs the code of the contract at address `addr` on this contract's storage, sending `value` wei
+/// to this contract itself. See [`call`] for the arguments and the returned value.
+pub fn call_code(
+    gas: u64,
+    addr: u64,
+    value: U256,
+    in_mem: u64,
+    in_size: u64,
+    out_mem: u64,
+    out_size: u64,
+) -> Result<u64, u64> {
+    types::flush_cells();
+    let value = value.to_be_bytes::<32>();
+    let success: u64;
+    let return_data_size: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") addr => success, inlateout("a1") value.as_ptr() as u64 => return_data_size, in("a2") in_mem, in("a3") in_size, in("a4") out_mem, in("a5") out_size, in("a6") gas, in("t0") u32::from(Syscall::CallCode));
+    }
+    types::clear_cells();
+    if success != 0 {
+        Ok(return_data_size)
+    } else {
+        Err(return_data_size)
+    }
+}
+
+/// Sends `value` wei to `to`, forwarding as much gas as possible and no call data.
+///
+/// Returns whether the transfer succeeded. If `to` is a contract, it may have reverted.
+pub fn send_eth(to: Address, value: U256) -> bool {
+    call(u64::MAX, to.as_ptr() as u64, value, 0, 0, 0, 0).is_ok()
+}
+
+/// Returns the balance of `addr`, in wei.
+pub fn balance(addr: Address) -> U256 {
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") addr.as_ptr() as u64, in("a1") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::Balance));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Returns the balance of the current contract, in wei.
+pub fn self_balance() -> U256 {
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::SelfBalance));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Returns the size of the code at `addr`, which is zero for accounts that are not contracts, and
+/// for contracts that are still being deployed.
+pub fn ext_code_size(addr: Address) -> u64 {
+    let size: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") addr.as_ptr() as u64 => size, in("t0") u32::from(Syscall::ExtCodeSize));
+    }
+    size
+}
+
+/// Returns the hash of the code at `addr`, or zero if the account does not exist.
+pub fn ext_code_hash(addr: Address) -> B256 {
+    let mut hash = B256::ZERO;
+    unsafe {
+        asm!("ecall", in("a0") addr.as_ptr() as u64, in("a1") hash.as_mut_ptr() as u64, in("t0") u32::from(Syscall::ExtCodeHash));
+    }
+    hash
+}
+
+/// Copies the code at `addr`, starting at `offset`, to `dest`. Bytes past the end of the code are
+/// zeroed.
+pub fn ext_code_copy(addr: Address, dest: &mut [u8], offset: u64) {
+    unsafe {
+        asm!("ecall", in("a0") addr.as_ptr() as u64, in("a1") dest.as_mut_ptr() as u64, in("a2") offset, in("a3") dest.len() as u64, in("t0") u32::from(Syscall::ExtCodeCopy));
+    }
+}
+
+/// Sends the balance of the current contract to `beneficiary` and stops execution.
+///
+/// Following EIP-6780, the contract is only deleted if it was created in the same transaction.
+pub fn selfdestruct(beneficiary: Address) -> ! {
+    types::flush_cells();
+    unsafe {
+        asm!("ecall", in("a0") beneficiary.as_ptr() as u64, in("t0") u32::from(Syscall::SelfDestruct));
+    }
+    unreachable!()
+}
+
+/// Returns the gas remaining in the current call.
+pub fn gas_left() -> u64 {
+    let gas: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") gas, in("t0") u32::from(Syscall::Gas));
+    }
+    gas
 }
 
-pub fn sstore(key: u64, value: u64) {
+/// Returns the size of the data returned by the last call.
+pub fn return_data_size() -> u64 {
+    let size: u64;
     unsafe {
-        asm!("ecall", in("a0") key, in("a1") value, in("t0") u32::from(Syscall::SStore));
+        asm!("ecall", lateout("a0") size, in("t0") u32::from(Syscall::ReturnDataSize));
     }
+    size
 }
 
-pub fn call(addr: u64, value: u64, in_mem: u64, in_size: u64, out_mem: u64, out_size: u64) {
+/// Copies the data returned by the last call, starting at `offset`, to `dest`.
+///
+/// Halts the current call, consuming all of its gas, if the range is out of bounds of the
+/// returned data, like `RETURNDATACOPY` does.
+pub fn return_data_copy(dest: &mut [u8], offset: u64) {
     unsafe {
-        asm!("ecall", in("a0") addr, in("a1") value, in("a2") in_mem, in("a3") in_size, in("a4") out_mem, in("a5") out_size, in("t0") u32::from(Syscall::Call));
+        asm!("ecall", in("a0") dest.as_mut_ptr() as u64, in("a1") offset, in("a2") dest.len() as u64, in("t0") u32::from(Syscall::ReturnDataCopy));
     }
 }
 
-pub fn revert() -> ! {
+/// Returns the data returned by the last call, e.g. its revert reason.
+pub fn return_data() -> Vec<u8> {
+    let mut data = alloc::vec![0u8; return_data_size() as usize];
+    return_data_copy(&mut data, 0);
+    data
+}
+
+/// Reverts the current call, returning `data` to the caller, e.g. an ABI-encoded error.
+pub fn revert(data: &[u8]) -> ! {
     unsafe {
-        asm!("ecall", in("t0") u32::from(Syscall::Revert));
+        asm!("ecall", in("a0") data.as_ptr() as u64, in("a1") data.len() as u64, in("t0") u32::from(Syscall::Revert));
     }
     unreachable!()
 }
 
+/// Emits a log with up to four `topics` and the given `data`.
+pub fn log(topics: &[B256], data: &[u8]) {
+    assert!(topics.len() <= 4);
+    unsafe {
+        asm!("ecall", in("a0") data.as_ptr() as u64, in("a1") data.len() as u64, in("a2") topics.as_ptr() as u64, in("a3") topics.len() as u64, in("t0") u32::from(Syscall::Log));
+    }
+}
+
+/// Emits a Solidity event, ABI-encoding its indexed fields as topics and the rest as data.
+pub fn emit<T: SolEvent>(event: T) {
+    let log_data = event.encode_log_data();
+    log(log_data.topics(), &log_data.data);
+}
+
 pub fn msg_sender() -> Address {
     let first: u64;
     let second: u64;
@@ -80,6 +381,45 @@ pub fn msg_sender() -> Address {
     unsafe {
         asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u32::from(Syscall::Caller));
     }
+    address_from_regs(first, second, third)
+}
+
+/// Deploys a contract with `init_code`, sending it `value` wei.
+///
+/// Returns the address of the new contract, or `None` if its deployment failed.
+pub fn create(value: U256, init_code: &[u8]) -> Option<Address> {
+    types::flush_cells();
+    let value = value.to_be_bytes::<32>();
+    let first: u64;
+    let second: u64;
+    let third: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") value.as_ptr() as u64 => first, inlateout("a1") init_code.as_ptr() as u64 => second, inlateout("a2") init_code.len() as u64 => third, in("t0") u32::from(Syscall::Create));
+    }
+    types::clear_cells();
+    let address = address_from_regs(first, second, third);
+    (address != Address::ZERO).then_some(address)
+}
+
+/// Deploys a contract with `init_code` at an address derived from `salt`, sending it `value` wei.
+///
+/// Returns the address of the new contract, or `None` if its deployment failed.
+pub fn create2(value: U256, init_code: &[u8], salt: B256) -> Option<Address> {
+    types::flush_cells();
+    let value = value.to_be_bytes::<32>();
+    let first: u64;
+    let second: u64;
+    let third: u64;
+    unsafe {
+        asm!("ecall", inlateout("a0") value.as_ptr() as u64 => first, inlateout("a1") init_code.as_ptr() as u64 => second, inlateout("a2") init_code.len() as u64 => third, in("a3") salt.as_ptr() as u64, in("t0") u32::from(Syscall::Create2));
+    }
+    types::clear_cells();
+    let address = address_from_regs(first, second, third);
+    (address != Address::ZERO).then_some(address)
+}
+
+/// Rebuilds an address returned in three registers, the last one holding only 4 bytes.
+fn address_from_regs(first: u64, second: u64, third: u64) -> Address {
     let mut bytes = [0u8; 20];
     bytes[0..8].copy_from_slice(&first.to_be_bytes());
     bytes[8..16].copy_from_slice(&second.to_be_bytes());
@@ -87,14 +427,117 @@ pub fn msg_sender() -> Address {
     Address::from_slice(&bytes)
 }
 
+/// Returns the keccak256 hash of `data`, computed by the host.
+pub fn keccak256(data: &[u8]) -> B256 {
+    let mut hash = B256::ZERO;
+    unsafe {
+        asm!("ecall", in("a0") data.as_ptr() as u64, in("a1") data.len() as u64, in("a2") hash.as_mut_ptr() as u64, in("t0") u32::from(Syscall::Keccak256));
+    }
+    hash
+}
+
+/// Returns the amount of wei sent with the current call.
+pub fn msg_value() -> U256 {
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::CallValue));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Returns the sender of the transaction, which is never a contract.
+pub fn tx_origin() -> Address {
+    let first: u64;
+    let second: u64;
+    let third: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u32::from(Syscall::Origin));
+    }
+    address_from_regs(first, second, third)
+}
+
+/// Returns the gas price paid by the transaction.
+pub fn tx_gas_price() -> U256 {
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::GasPrice));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Returns the number of the current block.
+pub fn block_number() -> u64 {
+    let number: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") number, in("t0") u32::from(Syscall::Number));
+    }
+    number
+}
+
+/// Returns the block timestamp, in seconds since the Unix epoch.
+pub fn block_timestamp() -> u64 {
+    let timestamp: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") timestamp, in("t0") u32::from(Syscall::Timestamp));
+    }
+    timestamp
+}
+
+/// Returns the ID of the chain the transaction runs on, as defined by EIP-155.
+pub fn chain_id() -> u64 {
+    let id: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") id, in("t0") u32::from(Syscall::ChainId));
+    }
+    id
+}
+
+/// Returns the base fee of the current block, in wei per gas.
+pub fn block_base_fee() -> U256 {
+    let mut value = [0u8; 32];
+    unsafe {
+        asm!("ecall", in("a0") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::BaseFee));
+    }
+    U256::from_be_bytes(value)
+}
+
+/// Returns the address of the block beneficiary.
+pub fn block_coinbase() -> Address {
+    let first: u64;
+    let second: u64;
+    let third: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") first, lateout("a1") second, lateout("a2") third, in("t0") u32::from(Syscall::Coinbase));
+    }
+    address_from_regs(first, second, third)
+}
+
+/// Returns the gas limit of the current block.
+pub fn block_gas_limit() -> u64 {
+    let gas_limit: u64;
+    unsafe {
+        asm!("ecall", lateout("a0") gas_limit, in("t0") u32::from(Syscall::GasLimit));
+    }
+    gas_limit
+}
+
+/// Returns the RANDAO mix of the previous block, or the block difficulty before the merge.
+pub fn block_prevrandao() -> B256 {
+    let mut value = B256::ZERO;
+    unsafe {
+        asm!("ecall", in("a0") value.as_mut_ptr() as u64, in("t0") u32::from(Syscall::PrevRandao));
+    }
+    value
+}
+
 #[allow(non_snake_case)]
 #[no_mangle]
 fn DefaultHandler() {
-    revert();
+    revert(&[]);
 }
 
 #[allow(non_snake_case)]
 #[no_mangle]
 fn ExceptionHandler(_trap_frame: &riscv_rt::TrapFrame) -> ! {
-    revert();
+    revert(&[]);
 }
@@ -0,0 +1,158 @@
+//! Typed wrappers around the Ethereum precompiled contracts.
+//!
+//! Precompiles are called with [`static_call`], and failures, such as invalid points or
+//! signatures, or running out of gas, are reported as `None`.
+
+use crate::*;
+
+use alloy_core::primitives::{Address, FixedBytes, B256, U256};
+
+extern crate alloc;
+use alloc::vec::Vec;
+
+const ECRECOVER: u8 = 0x01;
+const SHA256: u8 = 0x02;
+const RIPEMD160: u8 = 0x03;
+const MODEXP: u8 = 0x05;
+const BN254_ADD: u8 = 0x06;
+const BN254_MUL: u8 = 0x07;
+const BN254_PAIRING: u8 = 0x08;
+const BLAKE2F: u8 = 0x09;
+
+/// A point on the bn254 curve, as its `[x, y]` coordinates. The point at infinity is `[0, 0]`.
+pub type G1Point = [U256; 2];
+
+/// A point on the bn254 twisted curve, as `[x_imaginary, x_real, y_imaginary, y_real]`.
+pub type G2Point = [U256; 4];
+
+/// Calls the precompile at `address` with `input`, returning its output, or `None` if it failed.
+fn call_precompile(address: u8, input: &[u8]) -> Option<Vec<u8>> {
+    let address = Address::with_last_byte(address);
+    match static_call(
+        u64::MAX,
+        address.as_ptr() as u64,
+        input.as_ptr() as u64,
+        input.len() as u64,
+        0,
+        0,
+    ) {
+        Ok(_) => Some(return_data()),
+        Err(_) => None,
+    }
+}
+
+fn encode_words(words: &[U256]) -> Vec<u8> {
+    words
+        .iter()
+        .flat_map(|word| word.to_be_bytes::<32>())
+        .collect()
+}
+
+fn decode_g1_point(output: &[u8]) -> Option<G1Point> {
+    if output.len() != 64 {
+        return None;
+    }
+    Some([
+        U256::from_be_slice(&output[..32]),
+        U256::from_be_slice(&output[32..]),
+    ])
+}
+
+/// Recovers the address that signed `hash`, like Solidity's `ecrecover`.
+///
+/// Returns `None` if the signature is invalid.
+pub fn ecrecover(hash: B256, v: u8, r: B256, s: B256) -> Option<Address> {
+    let mut input = [0u8; 128];
+    input[..32].copy_from_slice(hash.as_slice());
+    input[63] = v;
+    input[64..96].copy_from_slice(r.as_slice());
+    input[96..].copy_from_slice(s.as_slice());
+    let output = call_precompile(ECRECOVER, &input)?;
+    // The precompile returns nothing when the recovery fails.
+    if output.len() != 32 {
+        return None;
+    }
+    Some(Address::from_slice(&output[12..]))
+}
+
+/// Returns the SHA-256 hash of `data`.
+pub fn sha256(data: &[u8]) -> Option<B256> {
+    let output = call_precompile(SHA256, data)?;
+    (output.len() == 32).then(|| B256::from_slice(&output))
+}
+
+/// Returns the RIPEMD-160 hash of `data`.
+pub fn ripemd160(data: &[u8]) -> Option<FixedBytes<20>> {
+    let output = call_precompile(RIPEMD160, data)?;
+    // The hash is left-padded to 32 bytes.
+    (output.len() == 32).then(|| FixedBytes::from_slice(&output[12..]))
+}
+
+/// Returns `base ** exponent % modulus`, all of them big-endian numbers. The result has the same
+/// length as `modulus`.
+pub fn modexp(base: &[u8], exponent: &[u8], modulus: &[u8]) -> Option<Vec<u8>> {
+    let mut input = encode_words(&[
+        U256::from(base.len()),
+        U256::from(exponent.len()),
+        U256::from(modulus.len()),
+    ]);
+    input.extend_from_slice(base);
+    input.extend_from_slice(exponent);
+    input.extend_from_slice(modulus);
+    call_precompile(MODEXP, &input)
+}
+
+/// Adds two bn254 points.
+///
+/// Returns `None` if any of them is not on the curve.
+pub fn bn254_add(p1: G1Point, p2: G1Point) -> Option<G1Point> {
+    let input = encode_words(&[p1[0], p1[1], p2[0], p2[1]]);
+    decode_g1_point(&call_precompile(BN254_ADD, &input)?)
+}
+
+/// Multiplies a bn254 point by `scalar`.
+///
+/// Returns `None` if the point is not on the curve.
+pub fn bn254_mul(p: G1Point, scalar: U256) -> Option<G1Point> {
+    let input = encode_words(&[p[0], p[1], scalar]);
+    decode_g1_point(&call_precompile(BN254_MUL, &input)?)
+}
+
+/// Checks whether the product of the pairings of each `(G1Point, G2Point)` pair is one.
+///
+/// Returns `None` if any of the points is invalid.
+pub fn bn254_pairing(pairs: &[(G1Point, G2Point)]) -> Option<bool> {
+    let words: Vec<U256> = pairs
+        .iter()
+        .flat_map(|(p1, p2)| p1.iter().chain(p2.iter()).copied())
+        .collect();
+    let output = call_precompile(BN254_PAIRING, &encode_words(&words))?;
+    if output.len() != 32 {
+        return None;
+    }
+    Some(output[31] == 1)
+}
+
+/// Runs `rounds` rounds of the BLAKE2b compression function F on the state `h`, the message
+/// block `m` and the offset counters `t`. `f` marks the final block.
+///
+/// Returns the new state.
+pub fn blake2f(rounds: u32, h: [u64; 8], m: [u64; 16], t: [u64; 2], f: bool) -> Option<[u64; 8]> {
+    // The words are encoded in little-endian order, but the rounds in big-endian order.
+    let mut input = Vec::with_capacity(213);
+    input.extend_from_slice(&rounds.to_be_bytes());
+    for word in h.iter().chain(m.iter()).chain(t.iter()) {
+        input.extend_from_slice(&word.to_le_bytes());
+    }
+    input.push(f as u8);
+
+    let output = call_precompile(BLAKE2F, &input)?;
+    if output.len() != 64 {
+        return None;
+    }
+    let mut state = [0u64; 8];
+    for (word, bytes) in state.iter_mut().zip(output.chunks_exact(8)) {
+        *word = u64::from_le_bytes(bytes.try_into().unwrap());
+    }
+    Some(state)
+}
@@ -1,56 +1,884 @@
 use core::marker::PhantomData;
 use core::default::Default;
+use core::cell::Cell;
+use core::ops::{Deref, DerefMut};
+use core::ptr::{addr_of, addr_of_mut, NonNull};
 
 use crate::*;
 
-use alloy_core::primitives::Address;
-use tiny_keccak::{Hasher, Keccak};
+use alloy_core::primitives::ruint::nlimbs;
+use alloy_core::primitives::{Address, Bytes, FixedBytes, Signed, Uint, U256};
+use alloy_sol_types::{Panic, PanicKind, SolError};
 
 extern crate alloc;
-use alloc::vec::Vec;
+use alloc::{boxed::Box, string::String, vec::Vec};
+
+/// A type that can be a field of a `#[storage]` struct, which assigns it a storage position.
+pub trait StorageLayout {
+    /// Number of bytes the type takes in storage. Like in Solidity, types smaller than a slot are
+    /// packed together, while the others start at a new slot and take whole slots.
+    const SIZE: usize;
+
+    /// Creates the field stored at `slot`, `offset` bytes from the lowest-order byte of the slot.
+    fn allocate(slot: U256, offset: u8) -> Self;
+}
+
+/// A storage type that [`Mapping::at`] can return for a key, e.g. an inner mapping.
+///
+/// `StorageCell` doesn't implement it: each call to `at` would create a cell of its own, so that
+/// changes made through one would be overwritten by another, or never written back in a `&self`
+/// method. Values are read and written with [`Mapping::read`] and [`Mapping::write`] instead.
+pub trait MappingValue: StorageLayout {}
+
+impl<K, V> MappingValue for Mapping<K, V> {}
+impl<K, V> MappingValue for TransientMapping<K, V> {}
+impl<T: StorageEncode> MappingValue for StorageValue<T> {}
+impl<T> MappingValue for StorageVec<T> {}
+impl MappingValue for StorageBytes {}
+impl MappingValue for StorageString {}
+
+/// Computes the position of each field of a `#[storage]` struct, as a slot and an offset within
+/// it, from its size and the slot it is optionally pinned to, following Solidity's layout rules.
+///
+/// Panics if two fields overlap, which is a compile-time error when used in a constant.
+pub const fn layout<const N: usize>(fields: [(usize, Option<u64>); N]) -> [(u64, u8); N] {
+    let mut positions = [(0u64, 0u8); N];
+    let mut slot = 0u64;
+    let mut offset = 0usize;
+    let mut i = 0;
+    while i < N {
+        let (size, pinned) = fields[i];
+        if let Some(pinned) = pinned {
+            slot = pinned;
+            offset = 0;
+        }
+        // A field that doesn't fit in the rest of the slot, or that takes whole slots, starts at
+        // the next one.
+        if offset > 0 && (size >= 32 || offset + size > 32) {
+            slot += 1;
+            offset = 0;
+        }
+        positions[i] = (slot, offset as u8);
+        if size >= 32 {
+            slot += size.div_ceil(32) as u64;
+            offset = 0;
+        } else {
+            offset += size;
+        }
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < N {
+        let mut j = i + 1;
+        while j < N {
+            let (start_i, end_i) = byte_range(positions[i], fields[i].0);
+            let (start_j, end_j) = byte_range(positions[j], fields[j].0);
+            if start_i < end_j && start_j < end_i {
+                panic!("Two fields of the `#[storage]` struct overlap.");
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    positions
+}
+
+/// Returns the storage bytes taken by a field at `position`, counting from the start of slot 0.
+const fn byte_range(position: (u64, u8), size: usize) -> (u128, u128) {
+    let start = position.0 as u128 * 32 + position.1 as u128;
+    let size = if size >= 32 {
+        size.div_ceil(32) * 32
+    } else {
+        size
+    };
+    (start, start + size as u128)
+}
+
+/// Where values are stored: the persistent storage, or the transient storage.
+pub trait StorageArea {
+    fn load(slot: U256) -> U256;
+    fn store(slot: U256, value: U256);
+}
+
+/// The persistent storage, accessed with SLOAD and SSTORE.
+pub struct Persistent;
+
+impl StorageArea for Persistent {
+    fn load(slot: U256) -> U256 {
+        sload(slot)
+    }
+
+    fn store(slot: U256, value: U256) {
+        sstore(slot, value);
+    }
+}
+
+/// The transient storage, accessed with TLOAD and TSTORE, which is cleared at the end of the
+/// transaction.
+pub struct Transient;
+
+impl StorageArea for Transient {
+    fn load(slot: U256) -> U256 {
+        tload(slot)
+    }
+
+    fn store(slot: U256, value: U256) {
+        tstore(slot, value);
+    }
+}
+
+/// Reads the `size` bytes stored at `offset` bytes from the lowest-order byte of `slot`.
+pub fn load_packed<S: StorageArea>(slot: U256, offset: u8, size: usize) -> U256 {
+    let word = S::load(slot);
+    if size >= 32 {
+        return word;
+    }
+    (word >> (offset as usize * 8)) & low_bytes_mask(size)
+}
+
+/// Writes `value` to the `size` bytes at `offset` bytes from the lowest-order byte of `slot`,
+/// keeping the rest of the slot intact.
+pub fn store_packed<S: StorageArea>(slot: U256, offset: u8, size: usize, value: U256) {
+    if size >= 32 {
+        S::store(slot, value);
+    } else {
+        let shift = offset as usize * 8;
+        let mask = low_bytes_mask(size) << shift;
+        let word = S::load(slot);
+        S::store(slot, (word & !mask) | ((value << shift) & mask));
+    }
+}
+
+fn low_bytes_mask(size: usize) -> U256 {
+    (U256::from(1) << (size * 8)) - U256::from(1)
+}
 
 /// Implements a Solidity-like Mapping type.
 #[derive(Default)]
 pub struct Mapping<K, V> {
-    id: u64,
-    pd: PhantomData<(K, V)>
+    slot: U256,
+    pd: PhantomData<(K, V)>,
 }
 
-impl<K: ToBytes, V: Into<u64> + From<u64>> Mapping<K, V> {
-    pub fn encode_key(&self, key: K) -> u64 {
-        let key_bytes = key.to_bytes();
-        let id_bytes = self.id.to_le_bytes();
+impl<K: ToBytes, V> Mapping<K, V> {
+    /// Returns the slot of the value of `key`, like Solidity: `keccak256(pad32(key) ++ slot)`.
+    pub fn encode_key(&self, key: K) -> U256 {
+        encode_key(self.slot, key)
+    }
+}
 
-        // Concatenate the key bytes and id bytes
-        let mut concatenated = Vec::with_capacity(key_bytes.len() + id_bytes.len());
-        concatenated.extend_from_slice(&key_bytes);
-        concatenated.extend_from_slice(&id_bytes);
+impl<K: ToBytes, V: MappingValue> Mapping<K, V> {
+    /// Returns the storage value of `key`, e.g. the inner mapping of a nested mapping:
+    /// `allowance.at(owner).read(spender)`.
+    pub fn at(&self, key: K) -> V {
+        V::allocate(self.encode_key(key), 0)
+    }
+}
 
-        let mut output = [0u8; 32];
-        let mut hasher = Keccak::v256();
-        hasher.update(&concatenated);
-        hasher.finalize(&mut output);
+impl<K: ToBytes, V: StorageCodec> Mapping<K, V> {
+    pub fn read(&self, key: K) -> V {
+        V::decode::<Persistent>(self.encode_key(key), 0)
+    }
 
-        let mut bytes = [0u8; 8];
-        bytes.copy_from_slice(&output[..8]);
-        u64::from_le_bytes(bytes)
+    pub fn write(&self, key: K, value: V) {
+        value.encode::<Persistent>(self.encode_key(key), 0);
     }
+}
 
+impl<K, V> StorageLayout for Mapping<K, V> {
+    // The slot itself stays empty, it only seeds the slots of the values.
+    const SIZE: usize = 32;
+
+    fn allocate(slot: U256, _offset: u8) -> Self {
+        Mapping {
+            slot,
+            pd: PhantomData,
+        }
+    }
+}
+
+/// Implements a Mapping type in transient storage, which is cleared at the end of the transaction.
+#[derive(Default)]
+pub struct TransientMapping<K, V> {
+    slot: U256,
+    pd: PhantomData<(K, V)>,
+}
+
+impl<K: ToBytes, V> TransientMapping<K, V> {
+    pub fn encode_key(&self, key: K) -> U256 {
+        encode_key(self.slot, key)
+    }
+}
+
+impl<K: ToBytes, V: StorageCodec> TransientMapping<K, V> {
     pub fn read(&self, key: K) -> V {
-        sload(self.encode_key(key)).into()
+        V::decode::<Transient>(self.encode_key(key), 0)
     }
 
     pub fn write(&self, key: K, value: V) {
-        sstore(self.encode_key(key), value.into());
+        value.encode::<Transient>(self.encode_key(key), 0);
+    }
+}
+
+impl<K, V> StorageLayout for TransientMapping<K, V> {
+    const SIZE: usize = 32;
+
+    fn allocate(slot: U256, _offset: u8) -> Self {
+        TransientMapping {
+            slot,
+            pd: PhantomData,
+        }
+    }
+}
+
+/// A single value in storage, like a Solidity state variable.
+#[derive(Default)]
+pub struct StorageValue<T> {
+    slot: U256,
+    offset: u8,
+    pd: PhantomData<T>,
+}
+
+impl<T: StorageCodec> StorageValue<T> {
+    pub fn read(&self) -> T {
+        T::decode::<Persistent>(self.slot, self.offset)
+    }
+
+    pub fn write(&self, value: T) {
+        value.encode::<Persistent>(self.slot, self.offset);
+    }
+}
+
+impl<T: StorageEncode> StorageLayout for StorageValue<T> {
+    const SIZE: usize = T::SIZE;
+
+    fn allocate(slot: U256, offset: u8) -> Self {
+        StorageValue {
+            slot,
+            offset,
+            pd: PhantomData,
+        }
+    }
+}
+
+/// A single value in storage that is used like a plain Rust value through `Deref` and `DerefMut`.
+///
+/// The value is loaded from storage on first access and kept in memory. If it was borrowed
+/// mutably, it is written back at the end of the call to the `&mut self` method, and before every
+/// call to another contract, create and selfdestruct, so that a contract reentering this one sees
+/// it. It is loaded again after the call, since a reentrant call may have changed it.
+///
+/// References to the value must not be held across such calls: changes made through a mutable
+/// reference taken before the call are lost. It can't be the value of a [`Mapping`] either, see
+/// [`MappingValue`].
+pub struct StorageCell<T: 'static> {
+    state: &'static CellState<T>,
+}
+
+impl<T: StorageCodec + 'static> Default for StorageCell<T> {
+    fn default() -> Self {
+        Self::allocate(U256::ZERO, 0)
+    }
+}
+
+impl<T: StorageCodec + 'static> Deref for StorageCell<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // Loaded values are never freed, and only mutated through `deref_mut`.
+        unsafe { self.state.load().as_ref() }
+    }
+}
+
+impl<T: StorageCodec + 'static> DerefMut for StorageCell<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        let mut value = self.state.load();
+        self.state.dirty.set(true);
+        unsafe { value.as_mut() }
+    }
+}
+
+impl<T: StorageCodec + 'static> StorageLayout for StorageCell<T> {
+    const SIZE: usize = T::SIZE;
+
+    fn allocate(slot: U256, offset: u8) -> Self {
+        let state: &'static CellState<T> = Box::leak(Box::new(CellState {
+            slot,
+            offset,
+            value: Cell::new(None),
+            dirty: Cell::new(false),
+        }));
+        unsafe { (*addr_of_mut!(CACHED_CELLS)).push(state) };
+        StorageCell { state }
+    }
+}
+
+/// The state of a `StorageCell`, which is never freed so that it can be reached from the list of
+/// cached cells.
+struct CellState<T> {
+    slot: U256,
+    offset: u8,
+    /// The value loaded from storage, if any. Each load leaks a new allocation, so that references
+    /// to a value dropped from the cache stay valid.
+    value: Cell<Option<NonNull<T>>>,
+    dirty: Cell<bool>,
+}
+
+impl<T: StorageCodec> CellState<T> {
+    fn load(&self) -> NonNull<T> {
+        if let Some(value) = self.value.get() {
+            return value;
+        }
+        let value = T::decode::<Persistent>(self.slot, self.offset);
+        let value = NonNull::from(Box::leak(Box::new(value)));
+        self.value.set(Some(value));
+        value
+    }
+}
+
+/// A value cached in memory by a `StorageCell`.
+trait CachedValue {
+    /// Writes the value back to storage if it was borrowed mutably.
+    fn flush(&self);
+
+    /// Drops the value, so that it is loaded again on next access.
+    fn clear(&self);
+}
+
+impl<T: StorageCodec> CachedValue for CellState<T> {
+    fn flush(&self) {
+        if let Some(value) = self.value.get().filter(|_| self.dirty.get()) {
+            unsafe { value.as_ref() }.encode::<Persistent>(self.slot, self.offset);
+        }
+        self.dirty.set(false);
+    }
+
+    fn clear(&self) {
+        self.value.set(None);
+    }
+}
+
+// Every `StorageCell` created by the contract. Contracts run on a single thread.
+static mut CACHED_CELLS: Vec<&'static dyn CachedValue> = Vec::new();
+
+/// Writes the `StorageCell`s changed in memory back to storage.
+///
+/// `#[contract]` calls it at the end of `&mut self` methods, and the runtime before calling
+/// another contract.
+pub fn flush_cells() {
+    for cell in unsafe { &*addr_of!(CACHED_CELLS) } {
+        cell.flush();
+    }
+}
+
+/// Drops the values cached by the `StorageCell`s, after a call that may have changed them.
+pub(crate) fn clear_cells() {
+    for cell in unsafe { &*addr_of!(CACHED_CELLS) } {
+        cell.clear();
+    }
+}
+
+/// A dynamic-length array in storage, like a Solidity `T[]` state variable.
+///
+/// The length is stored at the array slot, and the elements from `keccak256(slot)` on. Like in
+/// Solidity, elements of up to 16 bytes are packed together.
+#[derive(Default)]
+pub struct StorageVec<T> {
+    slot: U256,
+    pd: PhantomData<T>,
+}
+
+impl<T: StorageCodec> StorageVec<T> {
+    pub fn len(&self) -> u64 {
+        sload(self.slot).saturating_to()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads the element at `index`, reverting with `Panic(0x32)` if it is out of bounds.
+    pub fn read(&self, index: u64) -> T {
+        self.check_bounds(index);
+        let (slot, offset) = self.element_position(index);
+        T::decode::<Persistent>(slot, offset)
+    }
+
+    /// Writes the element at `index`, reverting with `Panic(0x32)` if it is out of bounds.
+    pub fn write(&self, index: u64, value: T) {
+        self.check_bounds(index);
+        let (slot, offset) = self.element_position(index);
+        value.encode::<Persistent>(slot, offset);
+    }
+
+    pub fn push(&self, value: T) {
+        let len = self.len();
+        sstore(self.slot, U256::from(len + 1));
+        let (slot, offset) = self.element_position(len);
+        value.encode::<Persistent>(slot, offset);
     }
+
+    /// Removes the last element and returns it, or `None` if the array is empty.
+    pub fn pop(&self) -> Option<T> {
+        let len = self.len().checked_sub(1)?;
+        let (slot, offset) = self.element_position(len);
+        let value = T::decode::<Persistent>(slot, offset);
+        // Like Solidity, zero the element's storage.
+        for i in 0..T::SIZE.div_ceil(32) {
+            store_packed::<Persistent>(slot + U256::from(i), offset, T::SIZE, U256::ZERO);
+        }
+        sstore(self.slot, U256::from(len));
+        Some(value)
+    }
+
+    fn check_bounds(&self, index: u64) {
+        if index >= self.len() {
+            revert(&Panic::from(PanicKind::ArrayOutOfBounds).abi_encode());
+        }
+    }
+
+    fn element_position(&self, index: u64) -> (U256, u8) {
+        let data_slot = data_slot(self.slot);
+        if T::SIZE > 16 {
+            let slots = T::SIZE.div_ceil(32) as u64;
+            (data_slot + U256::from(index * slots), 0)
+        } else {
+            let per_slot = (32 / T::SIZE) as u64;
+            let offset = (index % per_slot) as usize * T::SIZE;
+            (data_slot + U256::from(index / per_slot), offset as u8)
+        }
+    }
+}
+
+impl<T> StorageLayout for StorageVec<T> {
+    const SIZE: usize = 32;
+
+    fn allocate(slot: U256, _offset: u8) -> Self {
+        StorageVec {
+            slot,
+            pd: PhantomData,
+        }
+    }
+}
+
+/// A byte array in storage, like a Solidity `bytes` state variable.
+///
+/// Like in Solidity, up to 31 bytes are stored in the array slot itself, along with twice the
+/// length in the lowest-order byte. Longer arrays store `2 * len + 1` in the array slot, and their
+/// bytes from `keccak256(slot)` on.
+#[derive(Default)]
+pub struct StorageBytes {
+    slot: U256,
+}
+
+impl StorageBytes {
+    pub fn len(&self) -> usize {
+        let word = sload(self.slot);
+        if word.bit(0) {
+            (word >> 1).saturating_to()
+        } else {
+            (word.byte(0) / 2) as usize
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn read(&self) -> Vec<u8> {
+        let word = sload(self.slot);
+        if !word.bit(0) {
+            let len = (word.byte(0) / 2) as usize;
+            return word.to_be_bytes::<32>()[..len].to_vec();
+        }
+
+        let len: usize = (word >> 1).saturating_to();
+        let data_slot = data_slot(self.slot);
+        let mut data = Vec::with_capacity(len.next_multiple_of(32));
+        for i in 0..len.div_ceil(32) {
+            data.extend_from_slice(&sload(data_slot + U256::from(i)).to_be_bytes::<32>());
+        }
+        data.truncate(len);
+        data
+    }
+
+    pub fn write(&self, data: &[u8]) {
+        // Clear the slots of the previous data, so that they don't linger in storage.
+        let old_len = self.len();
+        let data_slot = data_slot(self.slot);
+        let new_slots = if data.len() < 32 {
+            0
+        } else {
+            data.len().div_ceil(32)
+        };
+        if old_len >= 32 {
+            for i in new_slots..old_len.div_ceil(32) {
+                sstore(data_slot + U256::from(i), U256::ZERO);
+            }
+        }
+
+        if data.len() < 32 {
+            let mut word = [0u8; 32];
+            word[..data.len()].copy_from_slice(data);
+            word[31] = (data.len() * 2) as u8;
+            sstore(self.slot, U256::from_be_bytes(word));
+        } else {
+            sstore(self.slot, U256::from(data.len() * 2 + 1));
+            for (i, chunk) in data.chunks(32).enumerate() {
+                let mut word = [0u8; 32];
+                word[..chunk.len()].copy_from_slice(chunk);
+                sstore(data_slot + U256::from(i), U256::from_be_bytes(word));
+            }
+        }
+    }
+}
+
+impl StorageLayout for StorageBytes {
+    const SIZE: usize = 32;
+
+    fn allocate(slot: U256, _offset: u8) -> Self {
+        StorageBytes { slot }
+    }
+}
+
+/// A string in storage, like a Solidity `string` state variable. It has the same layout as
+/// [`StorageBytes`].
+#[derive(Default)]
+pub struct StorageString {
+    bytes: StorageBytes,
+}
+
+impl StorageString {
+    /// Returns the length of the string, in bytes.
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn read(&self) -> String {
+        String::from_utf8_lossy(&self.bytes.read()).into_owned()
+    }
+
+    pub fn write(&self, value: &str) {
+        self.bytes.write(value.as_bytes());
+    }
+}
+
+impl StorageLayout for StorageString {
+    const SIZE: usize = 32;
+
+    fn allocate(slot: U256, offset: u8) -> Self {
+        StorageString {
+            bytes: StorageBytes::allocate(slot, offset),
+        }
+    }
+}
+
+/// Returns the number of bytes taken by a struct whose fields have the given sizes, laid out like
+/// a `#[storage]` struct. Like in Solidity, structs always take whole slots.
+pub const fn layout_size<const N: usize>(sizes: [usize; N]) -> usize {
+    let mut fields = [(0usize, None); N];
+    let mut i = 0;
+    while i < N {
+        fields[i].0 = sizes[i];
+        i += 1;
+    }
+    let positions = layout(fields);
+
+    let mut end = 0;
+    let mut i = 0;
+    while i < N {
+        let (_, field_end) = byte_range(positions[i], sizes[i]);
+        if field_end > end {
+            end = field_end;
+        }
+        i += 1;
+    }
+    (end as usize).div_ceil(32) * 32
+}
+
+/// A value that can be written to storage, persistent or transient, at a position given as a slot
+/// and an offset from the lowest-order byte of the slot.
+pub trait StorageEncode {
+    /// Number of bytes the value takes in storage. Values of less than 32 bytes are packed with
+    /// their neighbours, while larger ones start at offset 0 and take whole slots.
+    const SIZE: usize;
+
+    fn encode<S: StorageArea>(&self, slot: U256, offset: u8);
+}
+
+/// A value that can be read from storage, persistent or transient, at a position given as a slot
+/// and an offset from the lowest-order byte of the slot.
+pub trait StorageDecode: Sized {
+    fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self;
+}
+
+/// A value that can be both written to and read from storage, e.g. the values of a [`Mapping`].
+///
+/// It is implemented for the integers, `bool`, `Address`, the `Uint`s and `Signed`s of up to 256
+/// bits that are a multiple of 8 (e.g. `U256` and `I256`), and the `FixedBytes` of up to 32 bytes
+/// (e.g. `B256`), like the value types of Solidity. It can be derived with
+/// `#[derive(StorageCodec)]` for structs, which are laid out like Solidity structs.
+pub trait StorageCodec: StorageEncode + StorageDecode {}
+
+impl<T: StorageEncode + StorageDecode> StorageCodec for T {}
+
+// Implemented for each size rather than generically, so that the types that don't fit in a slot
+// don't compile instead of panicking at runtime.
+macro_rules! impl_storage_codec_for_uint {
+    ($($bits:literal),*) => {
+        $(
+            impl StorageEncode for Uint<$bits, { nlimbs($bits) }> {
+                const SIZE: usize = $bits / 8;
+
+                fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+                    store_packed::<S>(slot, offset, Self::SIZE, U256::from(*self));
+                }
+            }
+
+            impl StorageDecode for Uint<$bits, { nlimbs($bits) }> {
+                fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+                    Self::from(load_packed::<S>(slot, offset, Self::SIZE))
+                }
+            }
+        )*
+    };
+}
+
+impl_storage_codec_for_uint!(
+    8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 96, 104, 112, 120, 128, 136, 144, 152, 160, 168,
+    176, 184, 192, 200, 208, 216, 224, 232, 240, 248, 256
+);
+
+macro_rules! impl_storage_codec_for_signed {
+    ($($bits:literal),*) => {
+        $(
+            impl StorageEncode for Signed<$bits, { nlimbs($bits) }> {
+                const SIZE: usize = $bits / 8;
+
+                fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+                    // Stored in two's complement, like in Solidity.
+                    store_packed::<S>(slot, offset, Self::SIZE, U256::from(self.into_raw()));
+                }
+            }
+
+            impl StorageDecode for Signed<$bits, { nlimbs($bits) }> {
+                fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+                    Self::from_raw(Uint::from(load_packed::<S>(slot, offset, Self::SIZE)))
+                }
+            }
+        )*
+    };
+}
+
+impl_storage_codec_for_signed!(
+    8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 96, 104, 112, 120, 128, 136, 144, 152, 160, 168,
+    176, 184, 192, 200, 208, 216, 224, 232, 240, 248, 256
+);
+
+macro_rules! impl_storage_codec_for_int {
+    ($($int:ty => $uint:ty),*) => {
+        $(
+            impl StorageEncode for $int {
+                const SIZE: usize = core::mem::size_of::<$int>();
+
+                fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+                    // Signed integers are stored in two's complement, like in Solidity.
+                    store_packed::<S>(slot, offset, Self::SIZE, U256::from(*self as $uint));
+                }
+            }
+
+            impl StorageDecode for $int {
+                fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+                    load_packed::<S>(slot, offset, Self::SIZE).to::<$uint>() as $int
+                }
+            }
+        )*
+    };
+}
+
+impl_storage_codec_for_int!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128
+);
+
+impl StorageEncode for bool {
+    const SIZE: usize = 1;
+
+    fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+        store_packed::<S>(slot, offset, Self::SIZE, U256::from(*self as u8));
+    }
+}
+
+impl StorageDecode for bool {
+    fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+        load_packed::<S>(slot, offset, Self::SIZE) != U256::ZERO
+    }
+}
+
+impl StorageEncode for Address {
+    const SIZE: usize = 20;
+
+    fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+        let value = U256::from_be_slice(self.as_slice());
+        store_packed::<S>(slot, offset, Self::SIZE, value);
+    }
+}
+
+impl StorageDecode for Address {
+    fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+        let word = load_packed::<S>(slot, offset, Self::SIZE).to_be_bytes::<32>();
+        Address::from_slice(&word[32 - Self::SIZE..])
+    }
+}
+
+macro_rules! impl_storage_codec_for_fixed_bytes {
+    ($($n:literal),*) => {
+        $(
+            impl StorageEncode for FixedBytes<$n> {
+                const SIZE: usize = $n;
+
+                fn encode<S: StorageArea>(&self, slot: U256, offset: u8) {
+                    let value = U256::from_be_slice(self.as_slice());
+                    store_packed::<S>(slot, offset, Self::SIZE, value);
+                }
+            }
+
+            impl StorageDecode for FixedBytes<$n> {
+                fn decode<S: StorageArea>(slot: U256, offset: u8) -> Self {
+                    let word = load_packed::<S>(slot, offset, Self::SIZE).to_be_bytes::<32>();
+                    FixedBytes::from_slice(&word[32 - $n..])
+                }
+            }
+        )*
+    };
 }
 
+impl_storage_codec_for_fixed_bytes!(
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+    27, 28, 29, 30, 31, 32
+);
+
+/// Returns the first slot of the data of a dynamic array at `slot`, like Solidity.
+fn data_slot(slot: U256) -> U256 {
+    U256::from_be_bytes(keccak256(&slot.to_be_bytes::<32>()).0)
+}
+
+fn encode_key<K: ToBytes>(slot: U256, key: K) -> U256 {
+    let mut data = key.to_bytes();
+    data.extend_from_slice(&slot.to_be_bytes::<32>());
+    U256::from_be_bytes(keccak256(&data).0)
+}
+
+/// Encodes a mapping key the way Solidity does before hashing it with the mapping slot: value
+/// types are padded to 32 bytes.
 pub trait ToBytes {
     fn to_bytes(&self) -> Vec<u8>;
 }
 
 impl ToBytes for Address {
     fn to_bytes(&self) -> Vec<u8> {
-        self.0.to_vec()
+        self.into_word().to_vec()
+    }
+}
+
+macro_rules! impl_to_bytes_for_uint {
+    ($($bits:literal),*) => {
+        $(
+            impl ToBytes for Uint<$bits, { nlimbs($bits) }> {
+                fn to_bytes(&self) -> Vec<u8> {
+                    U256::from(*self).to_be_bytes::<32>().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_uint!(
+    8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 96, 104, 112, 120, 128, 136, 144, 152, 160, 168,
+    176, 184, 192, 200, 208, 216, 224, 232, 240, 248, 256
+);
+
+macro_rules! impl_to_bytes_for_signed {
+    ($($bits:literal),*) => {
+        $(
+            impl ToBytes for Signed<$bits, { nlimbs($bits) }> {
+                fn to_bytes(&self) -> Vec<u8> {
+                    // Sign-extended, like in Solidity.
+                    let mut value = U256::from(self.into_raw());
+                    if self.is_negative() {
+                        value |= U256::MAX << $bits;
+                    }
+                    value.to_be_bytes::<32>().to_vec()
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_signed!(
+    8, 16, 24, 32, 40, 48, 56, 64, 72, 80, 88, 96, 104, 112, 120, 128, 136, 144, 152, 160, 168,
+    176, 184, 192, 200, 208, 216, 224, 232, 240, 248, 256
+);
+
+macro_rules! impl_to_bytes_for_int {
+    ($($int:ty),*) => {
+        $(
+            impl ToBytes for $int {
+                fn to_bytes(&self) -> Vec<u8> {
+                    // Signed integers are sign-extended, like in Solidity.
+                    let fill = if *self < (0 as $int) { 0xff } else { 0 };
+                    let mut bytes = alloc::vec![fill; 32];
+                    let value = self.to_be_bytes();
+                    bytes[32 - value.len()..].copy_from_slice(&value);
+                    bytes
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+impl ToBytes for bool {
+    fn to_bytes(&self) -> Vec<u8> {
+        U256::from(*self as u8).to_be_bytes::<32>().to_vec()
+    }
+}
+
+macro_rules! impl_to_bytes_for_fixed_bytes {
+    ($($n:literal),*) => {
+        $(
+            impl ToBytes for FixedBytes<$n> {
+                fn to_bytes(&self) -> Vec<u8> {
+                    // Fixed-size byte arrays are padded on the right.
+                    let mut bytes = alloc::vec![0u8; 32];
+                    bytes[..$n].copy_from_slice(self.as_slice());
+                    bytes
+                }
+            }
+        )*
+    };
+}
+
+impl_to_bytes_for_fixed_bytes!(
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26,
+    27, 28, 29, 30, 31, 32
+);
+
+// Dynamic keys are hashed without padding.
+impl ToBytes for String {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl ToBytes for Bytes {
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_vec()
     }
 }
@@ -46,10 +46,39 @@ macro_rules! syscalls {
 
 // Generate `Syscall` enum with supported syscalls and their numbers.
 // t0: 0, opcode for return, a0: memory address of data, a1: length of data, in bytes, doesn't return
-// t0: 1, opcode for sload, a0: storage key, returns 64-bit value in a0
-// t0: 2, opcode for sstore, a0: storage key, a1: storage value, returns nothing
-// t0: 3, opcode for call, args: TODO
-// t0: 4, opcode for revert, doesn't return
+// t0: 1, opcode for sload, a0: memory address of 32-byte key, a1: memory address where the 32-byte value is written, returns nothing
+// t0: 2, opcode for sstore, a0: memory address of 32-byte key, a1: memory address of 32-byte value, returns nothing
+// t0: 3, opcode for call, a0: memory address of 20-byte callee address, a1: memory address of 32-byte value, a2: memory address of call data, a3: length of call data, a4: memory address of return data, a5: max length of return data, a6: gas for the callee (capped to all but one 64th of the remaining gas), returns 1 in a0 on success, 0 otherwise, and the length of the return data in a1
+// t0: 4, opcode for revert, a0: memory address of revert data, a1: length of revert data, in bytes, doesn't return
+// t0: 5, opcode for caller, returns the caller address in a0, a1 and the first 4 bytes of a2
+// t0: 6, opcode for log, a0: memory address of data, a1: length of data, in bytes, a2: memory address of 32-byte topics, a3: number of topics (at most 4), returns nothing
+// t0: 7, opcode for returndatasize, returns the length of the data returned by the last call in a0
+// t0: 8, opcode for returndatacopy, a0: memory address to copy to, a1: offset in the return data, a2: length to copy, returns nothing
+// t0: 9, opcode for create, a0: memory address of 32-byte value, a1: memory address of init code, a2: length of init code, returns the new contract address in a0, a1 and the first 4 bytes of a2, or zero on failure
+// t0: 10, opcode for create2, same as create, with a3: memory address of 32-byte salt
+// t0: 11, opcode for delegatecall, same as call without the value, so a1: memory address of call data, a2: length of call data, a3: memory address of return data, a4: max length of return data, a5: gas for the callee
+// t0: 12, opcode for staticcall, same arguments and return values as delegatecall
+// t0: 13, opcode for callcode, same arguments and return values as call
+// t0: 14, opcode for origin, returns the transaction sender address in a0, a1 and the first 4 bytes of a2
+// t0: 15, opcode for number, returns the block number in a0
+// t0: 16, opcode for timestamp, returns the block timestamp in a0
+// t0: 17, opcode for chainid, returns the chain id in a0
+// t0: 18, opcode for basefee, a0: memory address where the 32-byte base fee is written, returns nothing
+// t0: 19, opcode for coinbase, returns the block beneficiary address in a0, a1 and the first 4 bytes of a2
+// t0: 20, opcode for gaslimit, returns the block gas limit in a0
+// t0: 21, opcode for prevrandao, a0: memory address where the 32-byte previous RANDAO value is written, returns nothing
+// t0: 22, opcode for gasprice, a0: memory address where the 32-byte transaction gas price is written, returns nothing
+// t0: 23, opcode for callvalue, a0: memory address where the 32-byte value sent with the call is written, returns nothing
+// t0: 24, opcode for balance, a0: memory address of 20-byte account address, a1: memory address where the 32-byte balance is written, returns nothing
+// t0: 25, opcode for selfbalance, a0: memory address where the 32-byte balance of the current contract is written, returns nothing
+// t0: 26, opcode for extcodesize, a0: memory address of 20-byte account address, returns the size of its code in a0
+// t0: 27, opcode for extcodehash, a0: memory address of 20-byte account address, a1: memory address where the 32-byte code hash is written (zero for non-existent accounts), returns nothing
+// t0: 28, opcode for extcodecopy, a0: memory address of 20-byte account address, a1: memory address to copy to, a2: offset in the code, a3: length to copy (zero-padded past the end of the code), returns nothing
+// t0: 29, opcode for keccak256, a0: memory address of data, a1: length of data, in bytes, a2: memory address where the 32-byte hash is written, returns nothing
+// t0: 30, opcode for tload, a0: memory address of 32-byte key, a1: memory address where the 32-byte transient value is written, returns nothing
+// t0: 31, opcode for tstore, a0: memory address of 32-byte key, a1: memory address of 32-byte transient value, returns nothing
+// t0: 32, opcode for selfdestruct, a0: memory address of 20-byte beneficiary address, doesn't return
+// t0: 33, opcode for gas, returns the remaining gas in a0
 syscalls!(
     (0, Return, "return"),
     (1, SLoad, "sload"),
@@ -57,4 +86,32 @@ syscalls!(
     (3, Call, "call"),
     (4, Revert, "revert"),
     (5, Caller, "caller"),
+    (6, Log, "log"),
+    (7, ReturnDataSize, "returndatasize"),
+    (8, ReturnDataCopy, "returndatacopy"),
+    (9, Create, "create"),
+    (10, Create2, "create2"),
+    (11, DelegateCall, "delegatecall"),
+    (12, StaticCall, "staticcall"),
+    (13, CallCode, "callcode"),
+    (14, Origin, "origin"),
+    (15, Number, "number"),
+    (16, Timestamp, "timestamp"),
+    (17, ChainId, "chainid"),
+    (18, BaseFee, "basefee"),
+    (19, Coinbase, "coinbase"),
+    (20, GasLimit, "gaslimit"),
+    (21, PrevRandao, "prevrandao"),
+    (22, GasPrice, "gasprice"),
+    (23, CallValue, "callvalue"),
+    (24, Balance, "balance"),
+    (25, SelfBalance, "selfbalance"),
+    (26, ExtCodeSize, "extcodesize"),
+    (27, ExtCodeHash, "extcodehash"),
+    (28, ExtCodeCopy, "extcodecopy"),
+    (29, Keccak256, "keccak256"),
+    (30, TLoad, "tload"),
+    (31, TStore, "tstore"),
+    (32, SelfDestruct, "selfdestruct"),
+    (33, Gas, "gas"),
 );
@@ -1,16 +1,29 @@
-use eth_riscv_interpreter::setup_from_elf;
+use eth_riscv_interpreter::{run, setup_from_elf, Halt};
 use revm::{
     handler::register::EvmHandler,
     interpreter::{
-        CallInputs, CallScheme, CallValue, Host, InstructionResult, Interpreter, InterpreterAction,
-        InterpreterResult, SharedMemory,
+        gas, CallInputs, CallScheme, CallValue, CreateInputs, CreateScheme, Host,
+        InstructionResult, Interpreter, InterpreterAction, InterpreterResult, SStoreResult,
+        SharedMemory,
+    },
+    primitives::{
+        address, keccak256, Address, Bytes, Env, ExecutionResult, Log, Output, SpecId, TransactTo,
+        B256, MAX_INITCODE_SIZE, U256,
     },
-    primitives::{address, Address, Bytes, ExecutionResult, Output, TransactTo, U256},
     Database, Evm, Frame, FrameOrResult, InMemoryDB,
 };
 use rvemu::{emulator::Emulator, exception::Exception};
 use std::{cell::RefCell, ops::Range, rc::Rc, sync::Arc};
 
+/// Charges `cost` to the frame being executed, halting it with `OutOfGas` if it can't be paid.
+macro_rules! syscall_gas {
+    ($interpreter:expr, $cost:expr) => {
+        if !$interpreter.gas.record_cost($cost) {
+            return out_of_gas($interpreter);
+        }
+    };
+}
+
 pub fn deploy_contract(db: &mut InMemoryDB, bytecode: Bytes) -> Address {
     let mut evm = Evm::builder()
         .with_db(db)
@@ -38,14 +51,53 @@ pub fn deploy_contract(db: &mut InMemoryDB, bytecode: Bytes) -> Address {
     }
 }
 
-pub fn run_tx(db: &mut InMemoryDB, addr: &Address, calldata: Vec<u8>) {
+/// Sends a transaction calling `addr` with `calldata` and `value` wei from 0x...07, returning its
+/// output and the logs it emitted, and panicking if it doesn't succeed.
+pub fn run_tx(
+    db: &mut InMemoryDB,
+    addr: &Address,
+    calldata: Vec<u8>,
+    value: U256,
+) -> (Bytes, Vec<Log>) {
+    match try_run_tx(db, addr, calldata, value) {
+        Ok(output) => output,
+        Err(result) => panic!("Unexpected result: {:?}", result),
+    }
+}
+
+/// Sends a transaction like [`run_tx`], returning its result if it doesn't succeed, e.g. because
+/// it reverted.
+pub fn try_run_tx(
+    db: &mut InMemoryDB,
+    addr: &Address,
+    calldata: Vec<u8>,
+    value: U256,
+) -> Result<(Bytes, Vec<Log>), ExecutionResult> {
+    try_run_tx_with_env(db, addr, calldata, value, SpecId::LATEST, |_| {})
+}
+
+/// Sends a transaction like [`try_run_tx`], under the rules of `spec_id` and in the environment
+/// left by `modify_env`, e.g. with a given block or chain ID.
+pub fn try_run_tx_with_env(
+    db: &mut InMemoryDB,
+    addr: &Address,
+    calldata: Vec<u8>,
+    value: U256,
+    spec_id: SpecId,
+    modify_env: impl FnOnce(&mut Env),
+) -> Result<(Bytes, Vec<Log>), ExecutionResult> {
     let mut evm = Evm::builder()
         .with_db(db)
-        .modify_tx_env(|tx| {
-            tx.caller = address!("0000000000000000000000000000000000000007");
-            tx.transact_to = TransactTo::Call(*addr);
-            tx.data = calldata.into();
-            tx.value = U256::from(0);
+        .with_spec_id(spec_id)
+        .modify_env(|env| {
+            // RISC-V contracts may deploy other RISC-V contracts, whose code is larger than EVM
+            // code.
+            env.cfg.limit_contract_code_size = Some(usize::MAX);
+            env.tx.caller = address!("0000000000000000000000000000000000000007");
+            env.tx.transact_to = TransactTo::Call(*addr);
+            env.tx.data = calldata.into();
+            env.tx.value = value;
+            modify_env(env);
         })
         .append_handler_register(handle_register)
         .build();
@@ -55,16 +107,27 @@ pub fn run_tx(db: &mut InMemoryDB, addr: &Address, calldata: Vec<u8>) {
     match result {
         ExecutionResult::Success {
             output: Output::Call(value),
+            logs,
             ..
-        } => println!("Tx result: {:?}", value),
-        result => panic!("Unexpected result: {:?}", result),
-    };
+        } => {
+            println!("Tx result: {:?}", value);
+            Ok((value, logs))
+        }
+        result => Err(result),
+    }
 }
 
 #[derive(Debug)]
 struct RVEmu {
     emu: Emulator,
-    returned_data_destiny: Option<Range<u64>>,
+    pending_action: Option<PendingAction>,
+}
+
+/// A call or create requested by the RISC-V contract, whose outcome it is waiting for.
+#[derive(Debug)]
+enum PendingAction {
+    Call { returned_data_destiny: Range<u64> },
+    Create,
 }
 
 fn riscv_context(frame: &Frame) -> Option<RVEmu> {
@@ -73,7 +136,7 @@ fn riscv_context(frame: &Frame) -> Option<RVEmu> {
         let emu = setup_from_elf(&interpreter.bytecode[1..], &interpreter.contract.input);
         Some(RVEmu {
             emu,
-            returned_data_destiny: None,
+            pending_action: None,
         })
     } else {
         None
@@ -106,10 +169,12 @@ pub fn handle_register<EXT, DB: Database>(handler: &mut EvmHandler<'_, EXT, DB>)
     });
 
     // execute riscv context or old logic.
+    let spec_id = handler.cfg.spec_id;
     let old_handle = handler.execution.execute_frame.clone();
     handler.execution.execute_frame = Arc::new(move |frame, memory, instraction_table, ctx| {
-        let result = if let Some(Some(riscv_context)) = call_stack.borrow_mut().first_mut() {
-            execute_riscv(riscv_context, frame.interpreter_mut(), memory, ctx)
+        // The frame being executed is always the last one pushed to the call stack.
+        let result = if let Some(Some(riscv_context)) = call_stack.borrow_mut().last_mut() {
+            execute_riscv(riscv_context, frame.interpreter_mut(), memory, ctx, spec_id)
         } else {
             old_handle(frame, memory, instraction_table, ctx)?
         };
@@ -125,14 +190,37 @@ pub fn handle_register<EXT, DB: Database>(handler: &mut EvmHandler<'_, EXT, DB>)
 fn execute_riscv(
     rvemu: &mut RVEmu,
     interpreter: &mut Interpreter,
-    shared_memory: &mut SharedMemory,
+    _shared_memory: &mut SharedMemory,
     host: &mut dyn Host,
+    spec_id: SpecId,
 ) -> InterpreterAction {
     let emu = &mut rvemu.emu;
-    let returned_data_destiny = &mut rvemu.returned_data_destiny;
-    if let Some(destiny) = std::mem::take(returned_data_destiny) {
-        let data = emu.cpu.bus.get_dram_slice(destiny).unwrap();
-        data.copy_from_slice(shared_memory.slice(0, data.len()))
+    let pending_action = &mut rvemu.pending_action;
+    match std::mem::take(pending_action) {
+        Some(PendingAction::Call {
+            returned_data_destiny: destiny,
+        }) => {
+            // Resuming after a call: its outcome pushed the success flag to the stack.
+            let success = interpreter
+                .stack
+                .pop()
+                .map_or(false, |flag| flag != U256::ZERO);
+            let return_data = &interpreter.return_data_buffer;
+            let size = std::cmp::min(destiny.end - destiny.start, return_data.len() as u64);
+            if let Some(data) = dram_slice(emu, destiny.start, size) {
+                data.copy_from_slice(&return_data[..size as usize]);
+            }
+            emu.cpu.xregs.write(10, success as u64);
+            emu.cpu.xregs.write(11, return_data.len() as u64);
+        }
+        Some(PendingAction::Create) => {
+            // Resuming after a create: its outcome pushed the new address, or zero, to the stack.
+            let address = interpreter.stack.pop().map_or(Address::ZERO, |word| {
+                Address::from_slice(&word.to_be_bytes::<32>()[12..])
+            });
+            write_address(emu, address);
+        }
+        None => {}
     }
 
     let return_revert = |interpreter: &mut Interpreter| {
@@ -148,37 +236,40 @@ fn execute_riscv(
 
     // Run emulator and capture ecalls
     loop {
-        let run_result = emu.start();
-        match run_result {
-            Err(Exception::EnvironmentCallFromMMode) => {
+        let (halt, gas_used) = run(emu, interpreter.gas.remaining());
+        syscall_gas!(interpreter, gas_used);
+        match halt {
+            Halt::Exception(Exception::EnvironmentCallFromMMode) => {
                 let t0: u64 = emu.cpu.xregs.read(5);
                 match t0 {
                     0 => {
                         // Syscall::Return
                         let ret_offset: u64 = emu.cpu.xregs.read(10);
                         let ret_size: u64 = emu.cpu.xregs.read(11);
-                        let data_bytes = if ret_size != 0 {
-                            emu.cpu
-                                .bus
-                                .get_dram_slice(ret_offset..(ret_offset + ret_size))
-                                .unwrap()
-                        } else {
-                            &mut []
+                        let Some(data_bytes) = dram_slice(emu, ret_offset, ret_size) else {
+                            return halt(interpreter, InstructionResult::OutOfOffset);
                         };
                         return InterpreterAction::Return {
                             result: InterpreterResult {
                                 result: InstructionResult::Return,
                                 output: data_bytes.to_vec().into(),
-                                gas: interpreter.gas, // FIXME: gas is not correct
+                                gas: interpreter.gas,
                             },
                         };
                     }
                     1 => {
                         // Syscall:SLoad
-                        let key: u64 = emu.cpu.xregs.read(10);
-                        match host.sload(interpreter.contract.target_address, U256::from(key)) {
-                            Some((value, _is_cold)) => {
-                                emu.cpu.xregs.write(10, value.as_limbs()[0]);
+                        let key_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let Some(key) = read_word(emu, key_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        match host.sload(interpreter.contract.target_address, key) {
+                            Some((value, is_cold)) => {
+                                syscall_gas!(interpreter, gas::sload_cost(spec_id, is_cold));
+                                if write_word(emu, value_offset, value).is_none() {
+                                    return return_revert(interpreter);
+                                }
                             }
                             _ => {
                                 return return_revert(interpreter);
@@ -187,74 +278,556 @@ fn execute_riscv(
                     }
                     2 => {
                         // Syscall::SStore
-                        let key: u64 = emu.cpu.xregs.read(10);
-                        let value: u64 = emu.cpu.xregs.read(11);
-                        host.sstore(
-                            interpreter.contract.target_address,
-                            U256::from(key),
-                            U256::from(value),
-                        );
+                        if interpreter.is_static {
+                            return halt(
+                                interpreter,
+                                InstructionResult::StateChangeDuringStaticCall,
+                            );
+                        }
+                        let key_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let (Some(key), Some(value)) =
+                            (read_word(emu, key_offset), read_word(emu, value_offset))
+                        else {
+                            return return_revert(interpreter);
+                        };
+                        let Some(SStoreResult {
+                            original_value: original,
+                            present_value: old,
+                            new_value: new,
+                            is_cold,
+                        }) = host.sstore(interpreter.contract.target_address, key, value)
+                        else {
+                            return return_revert(interpreter);
+                        };
+                        let remaining = interpreter.gas.remaining();
+                        let Some(cost) =
+                            gas::sstore_cost(spec_id, original, old, new, remaining, is_cold)
+                        else {
+                            return out_of_gas(interpreter);
+                        };
+                        syscall_gas!(interpreter, cost);
+                        interpreter
+                            .gas
+                            .record_refund(gas::sstore_refund(spec_id, original, old, new));
                     }
-                    3 => {
-                        // Syscall::Call
-                        let a0: u64 = emu.cpu.xregs.read(10);
-                        let address =
-                            Address::from_slice(emu.cpu.bus.get_dram_slice(a0..(a0 + 20)).unwrap());
-                        let value: u64 = emu.cpu.xregs.read(11);
-                        let args_offset: u64 = emu.cpu.xregs.read(12);
-                        let args_size: u64 = emu.cpu.xregs.read(13);
-                        let ret_offset = emu.cpu.xregs.read(14);
-                        let ret_size = emu.cpu.xregs.read(15);
-
-                        *returned_data_destiny = Some(ret_offset..(ret_offset + ret_size));
-
-                        let tx = &host.env().tx;
+                    3 | 11 | 12 | 13 => {
+                        // Syscall::Call, Syscall::DelegateCall, Syscall::StaticCall and
+                        // Syscall::CallCode
+                        let scheme = match t0 {
+                            11 => CallScheme::DelegateCall,
+                            12 => CallScheme::StaticCall,
+                            13 => CallScheme::CallCode,
+                            _ => CallScheme::Call,
+                        };
+                        // Delegate and static calls take no value, so their other arguments
+                        // start one register earlier.
+                        let has_value = matches!(scheme, CallScheme::Call | CallScheme::CallCode);
+                        let args_reg = if has_value { 12 } else { 11 };
+                        let address_offset: u64 = emu.cpu.xregs.read(10);
+                        let value = if has_value {
+                            let value_offset: u64 = emu.cpu.xregs.read(11);
+                            let Some(value) = read_word(emu, value_offset) else {
+                                return return_revert(interpreter);
+                            };
+                            value
+                        } else {
+                            U256::ZERO
+                        };
+                        let args_offset: u64 = emu.cpu.xregs.read(args_reg);
+                        let args_size: u64 = emu.cpu.xregs.read(args_reg + 1);
+                        let ret_offset: u64 = emu.cpu.xregs.read(args_reg + 2);
+                        let ret_size: u64 = emu.cpu.xregs.read(args_reg + 3);
+                        let requested_gas: u64 = emu.cpu.xregs.read(args_reg + 4);
+                        let Some(address) = read_address(emu, address_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some(input) = dram_slice(emu, args_offset, args_size) else {
+                            return return_revert(interpreter);
+                        };
+                        let input = Bytes::copy_from_slice(input);
+                        // The returned data is copied there when the call returns, so the range
+                        // must be valid.
+                        if dram_slice(emu, ret_offset, ret_size).is_none() {
+                            return halt(interpreter, InstructionResult::OutOfOffset);
+                        }
+
+                        let transfers_value = value != U256::ZERO;
+                        if interpreter.is_static && scheme == CallScheme::Call && transfers_value {
+                            return halt(
+                                interpreter,
+                                InstructionResult::CallNotAllowedInsideStatic,
+                            );
+                        }
+
+                        let Some(account) = host.load_account(address) else {
+                            return return_revert(interpreter);
+                        };
+                        syscall_gas!(
+                            interpreter,
+                            gas::call_cost(
+                                spec_id,
+                                transfers_value,
+                                account.is_cold,
+                                // Only a plain call can bring a new account into existence.
+                                scheme == CallScheme::Call && account.is_empty
+                            )
+                        );
+                        // EIP-150: the callee gets at most all but one 64th of the remaining gas,
+                        // plus the stipend if it receives value. Unused gas is given back when the
+                        // call returns.
+                        let remaining = interpreter.gas.remaining();
+                        let mut gas_limit =
+                            std::cmp::min(requested_gas, remaining - remaining / 64);
+                        syscall_gas!(interpreter, gas_limit);
+                        if transfers_value {
+                            gas_limit = gas_limit.saturating_add(gas::CALL_STIPEND);
+                        }
+
+                        let this = interpreter.contract.target_address;
+                        let (target_address, caller, value) = match scheme {
+                            // Runs the callee's code on this contract's storage and balance, on
+                            // behalf of this contract's caller.
+                            CallScheme::DelegateCall => (
+                                this,
+                                interpreter.contract.caller,
+                                CallValue::Apparent(interpreter.contract.call_value),
+                            ),
+                            // Runs the callee's code on this contract's storage and balance.
+                            CallScheme::CallCode => (this, this, CallValue::Transfer(value)),
+                            _ => (address, this, CallValue::Transfer(value)),
+                        };
+
+                        *pending_action = Some(PendingAction::Call {
+                            returned_data_destiny: ret_offset..(ret_offset + ret_size),
+                        });
+
                         return InterpreterAction::Call {
                             inputs: Box::new(CallInputs {
-                                input: emu
-                                    .cpu
-                                    .bus
-                                    .get_dram_slice(args_offset..(args_offset + args_size))
-                                    .unwrap()
-                                    .to_vec()
-                                    .into(),
-                                gas_limit: tx.gas_limit,
-                                target_address: address,
+                                input,
+                                gas_limit,
+                                target_address,
                                 bytecode_address: address,
-                                caller: interpreter.contract.target_address,
-                                value: CallValue::Transfer(U256::from_le_bytes(
-                                    value.to_le_bytes(),
-                                )),
-                                scheme: CallScheme::Call,
-                                is_static: false,
+                                caller,
+                                value,
+                                scheme,
+                                // A static context extends to all of its nested calls.
+                                is_static: interpreter.is_static
+                                    || scheme == CallScheme::StaticCall,
                                 is_eof: false,
-                                return_memory_offset: 0..ret_size as usize,
+                                // The returned data is copied from the return data buffer instead.
+                                return_memory_offset: 0..0,
                             }),
                         };
                     }
                     4 => {
                         // Syscall::Revert
+                        let ret_offset: u64 = emu.cpu.xregs.read(10);
+                        let ret_size: u64 = emu.cpu.xregs.read(11);
+                        let Some(data_bytes) = dram_slice(emu, ret_offset, ret_size) else {
+                            return return_revert(interpreter);
+                        };
                         return InterpreterAction::Return {
                             result: InterpreterResult {
                                 result: InstructionResult::Revert,
-                                output: Bytes::from(0u32.to_le_bytes()), //TODO: return revert(0,0)
-                                gas: interpreter.gas, // FIXME: gas is not correct
+                                output: data_bytes.to_vec().into(),
+                                gas: interpreter.gas,
                             },
                         };
                     }
                     5 => {
                         // Syscall::Caller
-                        let caller = interpreter.contract.caller;
-                        // Break address into 3 u64s and write to registers
-                        let caller_bytes = caller.as_slice();
-                        let first_u64 = u64::from_be_bytes(caller_bytes[0..8].try_into().unwrap());
-                        emu.cpu.xregs.write(10, first_u64);
-                        let second_u64 = u64::from_be_bytes(caller_bytes[8..16].try_into().unwrap());
-                        emu.cpu.xregs.write(11, second_u64);
-                        let mut padded_bytes = [0u8; 8];
-                        padded_bytes[..4].copy_from_slice(&caller_bytes[16..20]);
-                        let third_u64 = u64::from_be_bytes(padded_bytes);
-                        emu.cpu.xregs.write(12, third_u64);
+                        syscall_gas!(interpreter, gas::BASE);
+                        write_address(emu, interpreter.contract.caller);
+                    }
+                    6 => {
+                        // Syscall::Log
+                        if interpreter.is_static {
+                            return halt(
+                                interpreter,
+                                InstructionResult::StateChangeDuringStaticCall,
+                            );
+                        }
+                        let data_offset: u64 = emu.cpu.xregs.read(10);
+                        let data_size: u64 = emu.cpu.xregs.read(11);
+                        let topics_offset: u64 = emu.cpu.xregs.read(12);
+                        let topics_count: u64 = emu.cpu.xregs.read(13);
+                        if topics_count > 4 {
+                            return return_revert(interpreter);
+                        }
+                        let Some(cost) = gas::log_cost(topics_count as u8, data_size) else {
+                            return out_of_gas(interpreter);
+                        };
+                        syscall_gas!(interpreter, cost);
+
+                        let Some(data) = dram_slice(emu, data_offset, data_size) else {
+                            return return_revert(interpreter);
+                        };
+                        let data = Bytes::copy_from_slice(data);
+                        let Some(topics) = dram_slice(emu, topics_offset, topics_count * 32) else {
+                            return return_revert(interpreter);
+                        };
+                        let topics = topics.chunks_exact(32).map(B256::from_slice).collect();
+
+                        host.log(Log::new_unchecked(
+                            interpreter.contract.target_address,
+                            topics,
+                            data,
+                        ));
+                    }
+                    7 => {
+                        // Syscall::ReturnDataSize
+                        syscall_gas!(interpreter, gas::BASE);
+                        let size = interpreter.return_data_buffer.len() as u64;
+                        emu.cpu.xregs.write(10, size);
+                    }
+                    8 => {
+                        // Syscall::ReturnDataCopy
+                        let dest_offset: u64 = emu.cpu.xregs.read(10);
+                        let data_offset: u64 = emu.cpu.xregs.read(11);
+                        let size: u64 = emu.cpu.xregs.read(12);
+                        let Some(cost) = gas::verylowcopy_cost(size) else {
+                            return out_of_gas(interpreter);
+                        };
+                        syscall_gas!(interpreter, cost);
+
+                        let return_data = &interpreter.return_data_buffer;
+                        let Some(data_end) = data_offset
+                            .checked_add(size)
+                            .filter(|end| *end <= return_data.len() as u64)
+                        else {
+                            return halt(interpreter, InstructionResult::OutOfOffset);
+                        };
+                        let Some(dest) = dram_slice(emu, dest_offset, size) else {
+                            return halt(interpreter, InstructionResult::OutOfOffset);
+                        };
+                        dest.copy_from_slice(&return_data[data_offset as usize..data_end as usize]);
+                    }
+                    9 | 10 => {
+                        // Syscall::Create and Syscall::Create2
+                        // EIP-1014: Skinny CREATE2
+                        if t0 == 10 && !spec_id.is_enabled_in(SpecId::PETERSBURG) {
+                            return halt(interpreter, InstructionResult::NotActivated);
+                        }
+                        if interpreter.is_static {
+                            return halt(
+                                interpreter,
+                                InstructionResult::StateChangeDuringStaticCall,
+                            );
+                        }
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        let code_offset: u64 = emu.cpu.xregs.read(11);
+                        let code_size: u64 = emu.cpu.xregs.read(12);
+                        let Some(value) = read_word(emu, value_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        // EIP-3860: Limit and meter initcode
+                        if spec_id.is_enabled_in(SpecId::SHANGHAI) {
+                            let max_initcode_size = host
+                                .env()
+                                .cfg
+                                .limit_contract_code_size
+                                .map(|limit| limit.saturating_mul(2))
+                                .unwrap_or(MAX_INITCODE_SIZE);
+                            if code_size > max_initcode_size as u64 {
+                                return halt(
+                                    interpreter,
+                                    InstructionResult::CreateInitCodeSizeLimit,
+                                );
+                            }
+                        }
+                        let Some(init_code) = dram_slice(emu, code_offset, code_size) else {
+                            return return_revert(interpreter);
+                        };
+                        let init_code = Bytes::copy_from_slice(init_code);
+
+                        let words = code_size.div_ceil(32);
+                        let mut cost = gas::CREATE;
+                        if spec_id.is_enabled_in(SpecId::SHANGHAI) {
+                            cost += gas::INITCODE_WORD_COST * words;
+                        }
+                        let scheme = if t0 == 10 {
+                            let salt_offset: u64 = emu.cpu.xregs.read(13);
+                            let Some(salt) = read_word(emu, salt_offset) else {
+                                return return_revert(interpreter);
+                            };
+                            // The init code is hashed to compute the address.
+                            cost += gas::KECCAK256WORD * words;
+                            CreateScheme::Create2 { salt }
+                        } else {
+                            CreateScheme::Create
+                        };
+                        syscall_gas!(interpreter, cost);
+
+                        // EIP-150: all but one 64th of the remaining gas is given to the init code.
+                        let remaining = interpreter.gas.remaining();
+                        let gas_limit = remaining - remaining / 64;
+                        syscall_gas!(interpreter, gas_limit);
+
+                        *pending_action = Some(PendingAction::Create);
+
+                        return InterpreterAction::Create {
+                            inputs: Box::new(CreateInputs {
+                                caller: interpreter.contract.target_address,
+                                scheme,
+                                value,
+                                init_code,
+                                gas_limit,
+                            }),
+                        };
+                    }
+                    14 => {
+                        // Syscall::Origin
+                        syscall_gas!(interpreter, gas::BASE);
+                        write_address(emu, host.env().tx.caller);
+                    }
+                    15 => {
+                        // Syscall::Number
+                        syscall_gas!(interpreter, gas::BASE);
+                        let number = host.env().block.number.saturating_to::<u64>();
+                        emu.cpu.xregs.write(10, number);
+                    }
+                    16 => {
+                        // Syscall::Timestamp
+                        syscall_gas!(interpreter, gas::BASE);
+                        let timestamp = host.env().block.timestamp.saturating_to::<u64>();
+                        emu.cpu.xregs.write(10, timestamp);
+                    }
+                    17 => {
+                        // Syscall::ChainId
+                        syscall_gas!(interpreter, gas::BASE);
+                        emu.cpu.xregs.write(10, host.env().cfg.chain_id);
+                    }
+                    18 => {
+                        // Syscall::BaseFee
+                        // EIP-3198: BASEFEE opcode
+                        if !spec_id.is_enabled_in(SpecId::LONDON) {
+                            return halt(interpreter, InstructionResult::NotActivated);
+                        }
+                        syscall_gas!(interpreter, gas::BASE);
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        if write_word(emu, value_offset, host.env().block.basefee).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    19 => {
+                        // Syscall::Coinbase
+                        syscall_gas!(interpreter, gas::BASE);
+                        write_address(emu, host.env().block.coinbase);
+                    }
+                    20 => {
+                        // Syscall::GasLimit
+                        syscall_gas!(interpreter, gas::BASE);
+                        let gas_limit = host.env().block.gas_limit.saturating_to::<u64>();
+                        emu.cpu.xregs.write(10, gas_limit);
+                    }
+                    21 => {
+                        // Syscall::PrevRandao
+                        syscall_gas!(interpreter, gas::BASE);
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        let block = &host.env().block;
+                        // Before the merge, this was the block difficulty.
+                        let value = if spec_id.is_enabled_in(SpecId::MERGE) {
+                            U256::from_be_bytes(block.prevrandao.unwrap_or_default().0)
+                        } else {
+                            block.difficulty
+                        };
+                        if write_word(emu, value_offset, value).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    22 => {
+                        // Syscall::GasPrice
+                        syscall_gas!(interpreter, gas::BASE);
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        let gas_price = host.env().effective_gas_price();
+                        if write_word(emu, value_offset, gas_price).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    23 => {
+                        // Syscall::CallValue
+                        syscall_gas!(interpreter, gas::BASE);
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        let value = interpreter.contract.call_value;
+                        if write_word(emu, value_offset, value).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    24 => {
+                        // Syscall::Balance
+                        let address_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let Some(address) = read_address(emu, address_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some((balance, is_cold)) = host.balance(address) else {
+                            return return_revert(interpreter);
+                        };
+                        syscall_gas!(interpreter, gas::warm_cold_cost(is_cold));
+                        if write_word(emu, value_offset, balance).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    25 => {
+                        // Syscall::SelfBalance
+                        syscall_gas!(interpreter, gas::LOW);
+                        let value_offset: u64 = emu.cpu.xregs.read(10);
+                        let Some((balance, _)) = host.balance(interpreter.contract.target_address)
+                        else {
+                            return return_revert(interpreter);
+                        };
+                        if write_word(emu, value_offset, balance).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    26 => {
+                        // Syscall::ExtCodeSize
+                        let address_offset: u64 = emu.cpu.xregs.read(10);
+                        let Some(address) = read_address(emu, address_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some((code, is_cold)) = host.code(address) else {
+                            return return_revert(interpreter);
+                        };
+                        syscall_gas!(interpreter, gas::warm_cold_cost(is_cold));
+                        emu.cpu.xregs.write(10, code.len() as u64);
+                    }
+                    27 => {
+                        // Syscall::ExtCodeHash
+                        let address_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let Some(address) = read_address(emu, address_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some((code_hash, is_cold)) = host.code_hash(address) else {
+                            return return_revert(interpreter);
+                        };
+                        syscall_gas!(interpreter, gas::warm_cold_cost(is_cold));
+                        let code_hash = U256::from_be_bytes(code_hash.0);
+                        if write_word(emu, value_offset, code_hash).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    28 => {
+                        // Syscall::ExtCodeCopy
+                        let address_offset: u64 = emu.cpu.xregs.read(10);
+                        let dest_offset: u64 = emu.cpu.xregs.read(11);
+                        let code_offset: u64 = emu.cpu.xregs.read(12);
+                        let size: u64 = emu.cpu.xregs.read(13);
+                        let Some(address) = read_address(emu, address_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some((code, is_cold)) = host.code(address) else {
+                            return return_revert(interpreter);
+                        };
+                        let Some(cost) = gas::extcodecopy_cost(spec_id, size, is_cold) else {
+                            return out_of_gas(interpreter);
+                        };
+                        syscall_gas!(interpreter, cost);
+
+                        let Some(dest) = dram_slice(emu, dest_offset, size) else {
+                            return return_revert(interpreter);
+                        };
+                        // Like in the EVM, bytes past the end of the code are copied as zeros.
+                        let start = std::cmp::min(code_offset, code.len() as u64) as usize;
+                        let end = std::cmp::min(start + dest.len(), code.len());
+                        dest[..end - start].copy_from_slice(&code[start..end]);
+                        dest[end - start..].fill(0);
+                    }
+                    29 => {
+                        // Syscall::Keccak256
+                        let data_offset: u64 = emu.cpu.xregs.read(10);
+                        let data_size: u64 = emu.cpu.xregs.read(11);
+                        let hash_offset: u64 = emu.cpu.xregs.read(12);
+                        let Some(cost) = gas::keccak256_cost(data_size) else {
+                            return out_of_gas(interpreter);
+                        };
+                        syscall_gas!(interpreter, cost);
+
+                        let Some(data) = dram_slice(emu, data_offset, data_size) else {
+                            return return_revert(interpreter);
+                        };
+                        let hash = U256::from_be_bytes(keccak256(data).0);
+                        if write_word(emu, hash_offset, hash).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    30 => {
+                        // Syscall::TLoad
+                        // EIP-1153: Transient storage opcodes
+                        if !spec_id.is_enabled_in(SpecId::CANCUN) {
+                            return halt(interpreter, InstructionResult::NotActivated);
+                        }
+                        syscall_gas!(interpreter, gas::WARM_STORAGE_READ_COST);
+                        let key_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let Some(key) = read_word(emu, key_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        let value = host.tload(interpreter.contract.target_address, key);
+                        if write_word(emu, value_offset, value).is_none() {
+                            return return_revert(interpreter);
+                        }
+                    }
+                    31 => {
+                        // Syscall::TStore
+                        // EIP-1153: Transient storage opcodes
+                        if !spec_id.is_enabled_in(SpecId::CANCUN) {
+                            return halt(interpreter, InstructionResult::NotActivated);
+                        }
+                        if interpreter.is_static {
+                            return halt(
+                                interpreter,
+                                InstructionResult::StateChangeDuringStaticCall,
+                            );
+                        }
+                        syscall_gas!(interpreter, gas::WARM_STORAGE_READ_COST);
+                        let key_offset: u64 = emu.cpu.xregs.read(10);
+                        let value_offset: u64 = emu.cpu.xregs.read(11);
+                        let (Some(key), Some(value)) =
+                            (read_word(emu, key_offset), read_word(emu, value_offset))
+                        else {
+                            return return_revert(interpreter);
+                        };
+                        host.tstore(interpreter.contract.target_address, key, value);
+                    }
+                    32 => {
+                        // Syscall::SelfDestruct
+                        if interpreter.is_static {
+                            return halt(
+                                interpreter,
+                                InstructionResult::StateChangeDuringStaticCall,
+                            );
+                        }
+                        let beneficiary_offset: u64 = emu.cpu.xregs.read(10);
+                        let Some(beneficiary) = read_address(emu, beneficiary_offset) else {
+                            return return_revert(interpreter);
+                        };
+                        // Since Cancun (EIP-6780), the account is only deleted if it was created
+                        // in the same transaction. Otherwise only its balance is transferred.
+                        let Some(result) =
+                            host.selfdestruct(interpreter.contract.target_address, beneficiary)
+                        else {
+                            return return_revert(interpreter);
+                        };
+                        // EIP-3529: Reduction in refunds
+                        if !spec_id.is_enabled_in(SpecId::LONDON) && !result.previously_destroyed {
+                            interpreter.gas.record_refund(gas::SELFDESTRUCT);
+                        }
+                        syscall_gas!(interpreter, gas::selfdestruct_cost(spec_id, result));
+                        return InterpreterAction::Return {
+                            result: InterpreterResult {
+                                result: InstructionResult::SelfDestruct,
+                                output: Bytes::new(),
+                                gas: interpreter.gas,
+                            },
+                        };
+                    }
+                    33 => {
+                        // Syscall::Gas
+                        syscall_gas!(interpreter, gas::BASE);
+                        emu.cpu.xregs.write(10, interpreter.gas.remaining());
                     }
                     _ => {
                         println!("Unhandled syscall: {:?}", t0);
@@ -262,9 +835,75 @@ fn execute_riscv(
                     }
                 }
             }
+            Halt::OutOfGas => {
+                return out_of_gas(interpreter);
+            }
             _ => {
                 return return_revert(interpreter);
             }
         }
     }
 }
+
+/// Halts the frame with `OutOfGas`, consuming all of its remaining gas.
+fn out_of_gas(interpreter: &mut Interpreter) -> InterpreterAction {
+    halt(interpreter, InstructionResult::OutOfGas)
+}
+
+/// Halts the frame with the error `result`, consuming all of its remaining gas.
+fn halt(interpreter: &mut Interpreter, result: InstructionResult) -> InterpreterAction {
+    let remaining = interpreter.gas.remaining();
+    interpreter.gas.record_cost(remaining);
+    InterpreterAction::Return {
+        result: InterpreterResult {
+            result,
+            output: Bytes::new(),
+            gas: interpreter.gas,
+        },
+    }
+}
+
+/// Writes `address` to the registers a0, a1 and a2, as three big-endian u64s, the last one
+/// holding only the 4 remaining bytes.
+fn write_address(emu: &mut Emulator, address: Address) {
+    let bytes = address.as_slice();
+    emu.cpu
+        .xregs
+        .write(10, u64::from_be_bytes(bytes[0..8].try_into().unwrap()));
+    emu.cpu
+        .xregs
+        .write(11, u64::from_be_bytes(bytes[8..16].try_into().unwrap()));
+    let mut padded_bytes = [0u8; 8];
+    padded_bytes[..4].copy_from_slice(&bytes[16..20]);
+    emu.cpu.xregs.write(12, u64::from_be_bytes(padded_bytes));
+}
+
+/// Reads the 20-byte address at `offset` in the RISC-V memory.
+fn read_address(emu: &mut Emulator, offset: u64) -> Option<Address> {
+    let bytes = dram_slice(emu, offset, 20)?;
+    Some(Address::from_slice(bytes))
+}
+
+/// Returns the RISC-V memory in `offset..offset + size`, which may be empty.
+fn dram_slice(emu: &mut Emulator, offset: u64, size: u64) -> Option<&mut [u8]> {
+    if size == 0 {
+        return Some(&mut []);
+    }
+    emu.cpu
+        .bus
+        .get_dram_slice(offset..offset.checked_add(size)?)
+        .ok()
+}
+
+/// Reads a 32-byte big-endian word from RISC-V memory at `offset`.
+fn read_word(emu: &mut Emulator, offset: u64) -> Option<U256> {
+    let bytes = dram_slice(emu, offset, 32)?;
+    Some(U256::from_be_slice(bytes))
+}
+
+/// Writes `value` as a 32-byte big-endian word to RISC-V memory at `offset`.
+fn write_word(emu: &mut Emulator, offset: u64, value: U256) -> Option<()> {
+    let bytes = dram_slice(emu, offset, 32)?;
+    bytes.copy_from_slice(&value.to_be_bytes::<32>());
+    Some(())
+}
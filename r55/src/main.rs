@@ -1,16 +1,31 @@
 mod exec;
-use exec::{deploy_contract, run_tx};
+use exec::{deploy_contract, run_tx, try_run_tx, try_run_tx_with_env};
 
 use std::fs::File;
 use std::io::Read;
 use std::process::Command;
 
-use alloy_sol_types::SolValue;
+use alloy_core::primitives::I256;
+use alloy_sol_types::{sol, Panic, Revert, SolError, SolEvent, SolValue};
 use revm::{
-    primitives::{address, keccak256, ruint::Uint, AccountInfo, Address, Bytecode, Bytes},
+    primitives::{
+        address, b256, fixed_bytes, keccak256, ruint::Uint, AccountInfo, Address, Bytecode, Bytes,
+        Env, ExecutionResult, FixedBytes, HaltReason, SpecId, B256, U256,
+    },
     InMemoryDB,
 };
 
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+    event Approval(address indexed owner, address indexed spender, uint256 value);
+}
+
+/// The sender of the test transactions.
+const TX_SENDER: Address = address!("0000000000000000000000000000000000000007");
+const ERC20_ADDR: Address = address!("0d4a11d5EEaaC28EC3F61d100daF4d40471f1852");
+const ERC20X_ADDR: Address = address!("1e6f2b9a45e8a0c4b1f8e0b5c6d9e8f7a6b5c4d3");
+const TESTBENCH_ADDR: Address = address!("3a8b4d9c67fac2e6d3baf2d7e8fb0a9b8c7d6e5f");
+
 fn compile_runtime(path: &str) -> Result<Vec<u8>, ()> {
     println!("Compiling runtime: {}", path);
     let status = Command::new("cargo")
@@ -104,6 +119,11 @@ fn compile_deploy(path: &str) -> Result<Vec<u8>, ()> {
     Ok(bytecode)
 }
 
+/// Returns the Solidity function selector for `signature`, e.g. `balanceOf(address)`.
+fn get_selector(signature: &str) -> [u8; 4] {
+    keccak256(signature)[..4].try_into().unwrap()
+}
+
 fn add_contract_to_db(db: &mut InMemoryDB, addr: Address, bytecode: Bytes) {
     let account = AccountInfo::new(
         Uint::from(0),
@@ -114,6 +134,43 @@ fn add_contract_to_db(db: &mut InMemoryDB, addr: Address, bytecode: Bytes) {
     db.insert_account_info(addr, account);
 }
 
+/// Compiles the RISC-V contract at `path` and adds its runtime code to `db` at `addr`, returning
+/// the code.
+fn add_riscv_contract_to_db(db: &mut InMemoryDB, addr: Address, path: &str) -> Bytes {
+    let mut bytecode = vec![0xff];
+    bytecode.extend_from_slice(&compile_runtime(path).unwrap());
+    let bytecode = Bytes::from(bytecode);
+    add_contract_to_db(db, addr, bytecode.clone());
+    bytecode
+}
+
+/// Returns the calldata calling the method with the Solidity `signature` with the ABI-encoded
+/// `args`.
+fn encode_call(signature: &str, args: Vec<u8>) -> Vec<u8> {
+    let mut calldata = get_selector(signature).to_vec();
+    calldata.extend_from_slice(&args);
+    calldata
+}
+
+/// Calls the method with the Solidity `signature` of the contract at `addr` with the ABI-encoded
+/// `args`, returning the ABI-encoded result.
+fn call_method(db: &mut InMemoryDB, addr: Address, signature: &str, args: Vec<u8>) -> Bytes {
+    run_tx(db, &addr, encode_call(signature, args), U256::ZERO).0
+}
+
+/// Returns a database holding the `testbench` contract at `TESTBENCH_ADDR`.
+fn testbench_db() -> InMemoryDB {
+    let mut db = InMemoryDB::default();
+    add_riscv_contract_to_db(&mut db, TESTBENCH_ADDR, "testbench");
+    db
+}
+
+/// Calls the method with the Solidity `signature` of the `testbench` contract, like
+/// [`call_method`].
+fn call_testbench(db: &mut InMemoryDB, signature: &str, args: Vec<u8>) -> Bytes {
+    call_method(db, TESTBENCH_ADDR, signature, args)
+}
+
 fn test_runtime_from_binary() {
     let rv_bytecode = compile_runtime("erc20").unwrap();
 
@@ -127,23 +184,33 @@ fn test_runtime_from_binary() {
 
     add_contract_to_db(&mut db, CONTRACT_ADDR, bytecode);
 
-    let selector_balance: u32 = 0;
-    let selector_mint: u32 = 2;
+    let selector_balance = get_selector("balanceOf(address)");
+    let selector_mint = get_selector("mint(address,uint256)");
     let to: Address = address!("0000000000000000000000000000000000000001");
-    let value_mint: u64 = 42;
+    let value_mint = U256::from(42);
     let mut calldata_balance = to.abi_encode();
     let mut calldata_mint = (to, value_mint).abi_encode();
 
-    let selector_bytes_balance = selector_balance.to_le_bytes().to_vec();
+    let selector_bytes_balance = selector_balance.to_vec();
     let mut complete_calldata_balance = selector_bytes_balance;
     complete_calldata_balance.append(&mut calldata_balance);
 
-    let selector_bytes_mint = selector_mint.to_le_bytes().to_vec();
+    let selector_bytes_mint = selector_mint.to_vec();
     let mut complete_calldata_mint = selector_bytes_mint;
     complete_calldata_mint.append(&mut calldata_mint);
 
-    run_tx(&mut db, &CONTRACT_ADDR, complete_calldata_mint.clone());
-    run_tx(&mut db, &CONTRACT_ADDR, complete_calldata_balance.clone());
+    run_tx(
+        &mut db,
+        &CONTRACT_ADDR,
+        complete_calldata_mint.clone(),
+        U256::ZERO,
+    );
+    run_tx(
+        &mut db,
+        &CONTRACT_ADDR,
+        complete_calldata_balance.clone(),
+        U256::ZERO,
+    );
 
     /*
     let account_db = &evm.db().accounts[&CONTRACT_ADDR];
@@ -154,23 +221,23 @@ fn test_runtime_from_binary() {
 }
 
 fn test_runtime(addr: &Address, db: &mut InMemoryDB) {
-    let selector_balance: u32 = 0;
-    let selector_mint: u32 = 2;
+    let selector_balance = get_selector("balanceOf(address)");
+    let selector_mint = get_selector("mint(address,uint256)");
     let to: Address = address!("0000000000000000000000000000000000000001");
-    let value_mint: u64 = 42;
+    let value_mint = U256::from(42);
     let mut calldata_balance = to.abi_encode();
     let mut calldata_mint = (to, value_mint).abi_encode();
 
-    let selector_bytes_balance = selector_balance.to_le_bytes().to_vec();
+    let selector_bytes_balance = selector_balance.to_vec();
     let mut complete_calldata_balance = selector_bytes_balance;
     complete_calldata_balance.append(&mut calldata_balance);
 
-    let selector_bytes_mint = selector_mint.to_le_bytes().to_vec();
+    let selector_bytes_mint = selector_mint.to_vec();
     let mut complete_calldata_mint = selector_bytes_mint;
     complete_calldata_mint.append(&mut calldata_mint);
 
-    run_tx(db, addr, complete_calldata_mint.clone());
-    run_tx(db, addr, complete_calldata_balance.clone());
+    run_tx(db, addr, complete_calldata_mint.clone(), U256::ZERO);
+    run_tx(db, addr, complete_calldata_balance.clone(), U256::ZERO);
 }
 
 fn test_deploy() {
@@ -187,7 +254,826 @@ fn test_deploy() {
     test_runtime(&addr, &mut db);
 }
 
+/// EVM bytecode that forwards its calldata to `target` and returns whatever `target` returned.
+fn evm_forwarder(target: Address) -> Bytes {
+    let mut bytecode = vec![
+        0x36, 0x5f, 0x5f, 0x37, // CALLDATACOPY(0, 0, CALLDATASIZE)
+        0x5f, 0x5f, 0x36, 0x5f, 0x5f, // retSize, retOffset, argsSize, argsOffset, value
+        0x73, // PUSH20 target
+    ];
+    bytecode.extend_from_slice(target.as_slice());
+    bytecode.extend_from_slice(&[
+        0x5a, 0xf1, 0x50, // CALL(GAS, ...), ignoring the success flag
+        0x3d, 0x5f, 0x5f, 0x3e, // RETURNDATACOPY(0, 0, RETURNDATASIZE)
+        0x3d, 0x5f, 0xf3, // RETURN(0, RETURNDATASIZE)
+    ]);
+    bytecode.into()
+}
+
+fn test_nested_calls() {
+    const FORWARDER_ADDR: Address = address!("2f7a3c8b56f9b1d5c2a9f1c6d7eaf9a8b7c6d5e4");
+    let mut db = InMemoryDB::default();
+    add_riscv_contract_to_db(&mut db, ERC20_ADDR, "erc20");
+    add_riscv_contract_to_db(&mut db, ERC20X_ADDR, "erc20x");
+    add_contract_to_db(&mut db, FORWARDER_ADDR, evm_forwarder(ERC20_ADDR));
+
+    let owner: Address = address!("0000000000000000000000000000000000000001");
+    call_method(
+        &mut db,
+        ERC20_ADDR,
+        "mint(address,uint256)",
+        (owner, U256::from(42)).abi_encode(),
+    );
+
+    let check_balance = |db: &mut InMemoryDB, signature: &str, args: Vec<u8>| {
+        let result = call_method(db, ERC20X_ADDR, signature, args);
+        assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(42));
+    };
+
+    // RISC-V -> RISC-V
+    check_balance(
+        &mut db,
+        "xBalanceOf(address,address)",
+        (owner, ERC20_ADDR).abi_encode(),
+    );
+    // RISC-V -> EVM -> RISC-V
+    check_balance(
+        &mut db,
+        "xBalanceOf(address,address)",
+        (owner, FORWARDER_ADDR).abi_encode(),
+    );
+    // RISC-V -> RISC-V (re-entering the same contract) -> RISC-V
+    check_balance(
+        &mut db,
+        "xBalanceOfVia(address,address,address)",
+        (owner, ERC20_ADDR, ERC20X_ADDR).abi_encode(),
+    );
+
+    let result = call_method(&mut db, ERC20_ADDR, "totalSupply()", Vec::new());
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(42));
+
+    call_method(
+        &mut db,
+        ERC20_ADDR,
+        "approve(address,uint256)",
+        (owner, U256::from(5)).abi_encode(),
+    );
+    let result = call_method(
+        &mut db,
+        ERC20_ADDR,
+        "allowance(address,address)",
+        (TX_SENDER, owner).abi_encode(),
+    );
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(5));
+
+    // A transfer of 0 tokens succeeds, unless it runs in a static context.
+    let calldata_transfer = encode_call(
+        "transfer(address,address,uint256)",
+        (ERC20X_ADDR, owner, U256::ZERO).abi_encode(),
+    );
+    let calldata_transfer = Bytes::from(calldata_transfer);
+    for (signature, succeeds) in [
+        ("callSucceeds(address,bytes)", true),
+        ("staticCallSucceeds(address,bytes)", false),
+    ] {
+        let args = (ERC20_ADDR, calldata_transfer.clone()).abi_encode_params();
+        let result = call_method(&mut db, ERC20X_ADDR, signature, args);
+        assert_eq!(bool::abi_decode(&result, true).unwrap(), succeeds);
+    }
+}
+
+fn test_events() {
+    let mut db = InMemoryDB::default();
+    add_riscv_contract_to_db(&mut db, ERC20_ADDR, "erc20");
+
+    // Each call emits a single log from the token, whose data decodes as the expected event.
+    let mut emit = |signature: &str, args: Vec<u8>| {
+        let calldata = encode_call(signature, args);
+        let (_, logs) = run_tx(&mut db, &ERC20_ADDR, calldata, U256::ZERO);
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].address, ERC20_ADDR);
+        // The signature and the two indexed addresses.
+        assert_eq!(logs[0].topics().len(), 3);
+        logs[0].data.clone()
+    };
+
+    let owner: Address = address!("0000000000000000000000000000000000000001");
+    let args = (owner, U256::from(42)).abi_encode();
+    let log = emit("mint(address,uint256)", args);
+    let event = Transfer::decode_log_data(&log, true).unwrap();
+    assert_eq!(
+        (event.from, event.to, event.value),
+        (Address::ZERO, owner, U256::from(42))
+    );
+
+    let args = (owner, TX_SENDER, U256::from(10)).abi_encode();
+    let log = emit("transfer(address,address,uint256)", args);
+    let event = Transfer::decode_log_data(&log, true).unwrap();
+    assert_eq!(
+        (event.from, event.to, event.value),
+        (owner, TX_SENDER, U256::from(10))
+    );
+
+    let args = (owner, U256::from(5)).abi_encode();
+    let log = emit("approve(address,uint256)", args);
+    let event = Approval::decode_log_data(&log, true).unwrap();
+    assert_eq!(
+        (event.owner, event.spender, event.value),
+        (TX_SENDER, owner, U256::from(5))
+    );
+}
+
+fn test_delegate_call() {
+    const TARGET_ADDR: Address = address!("4b9c5eab78fbd3f7e4cbf3e8f9fc1bac9d8e7f60");
+    let mut db = testbench_db();
+    add_riscv_contract_to_db(&mut db, TARGET_ADDR, "testbench");
+
+    let number = |db: &mut InMemoryDB, addr: Address| {
+        let result = call_method(db, addr, "number()", Vec::new());
+        U256::abi_decode(&result, true).unwrap()
+    };
+
+    // Both run the target's code on the caller's storage.
+    for (signature, new_number) in [
+        ("delegateCallSucceeds(address,bytes)", 7),
+        ("callCodeSucceeds(address,bytes)", 9),
+    ] {
+        let calldata_set = encode_call("setNumber(uint256)", U256::from(new_number).abi_encode());
+        let args = (TARGET_ADDR, Bytes::from(calldata_set)).abi_encode_params();
+        let result = call_testbench(&mut db, signature, args);
+        assert!(bool::abi_decode(&result, true).unwrap());
+
+        assert_eq!(number(&mut db, TESTBENCH_ADDR), U256::from(new_number));
+        assert_eq!(number(&mut db, TARGET_ADDR), U256::ZERO);
+    }
+}
+
+fn test_create() {
+    let mut db = testbench_db();
+
+    let mut init_code = vec![0xff];
+    init_code.extend_from_slice(&compile_deploy("erc20").unwrap());
+    let init_code = Bytes::from(init_code);
+
+    let nonce = db.accounts[&TESTBENCH_ADDR].info.nonce;
+    let args = (init_code.clone(),).abi_encode_params();
+    let result = call_testbench(&mut db, "deploy(bytes)", args);
+    let child = Address::abi_decode(&result, true).unwrap();
+    assert_eq!(child, TESTBENCH_ADDR.create(nonce));
+
+    let salt = B256::with_last_byte(1);
+    let args = (init_code.clone(), salt).abi_encode_params();
+    let result = call_testbench(&mut db, "deploy2(bytes,bytes32)", args);
+    let child2 = Address::abi_decode(&result, true).unwrap();
+    assert_eq!(child2, TESTBENCH_ADDR.create2_from_code(salt, &init_code));
+
+    // The children run the ERC20 runtime code returned by the init code.
+    let owner: Address = address!("0000000000000000000000000000000000000001");
+    for child in [child, child2] {
+        call_method(
+            &mut db,
+            child,
+            "mint(address,uint256)",
+            (owner, U256::from(42)).abi_encode(),
+        );
+        let result = call_method(&mut db, child, "balanceOf(address)", owner.abi_encode());
+        assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(42));
+    }
+}
+
+fn test_value_transfers() {
+    const RECIPIENT: Address = address!("00000000000000000000000000000000000000aa");
+    let mut db = testbench_db();
+    let sender = AccountInfo {
+        balance: U256::from(1000),
+        ..Default::default()
+    };
+    db.insert_account_info(TX_SENDER, sender);
+
+    // Like in Solidity, methods that are not `#[payable]` reject value.
+    let calldata = encode_call("number()", Vec::new());
+    let result = try_run_tx(&mut db, &TESTBENCH_ADDR, calldata, U256::from(1));
+    assert!(matches!(result, Err(ExecutionResult::Revert { .. })));
+
+    let calldata = encode_call("deposit()", Vec::new());
+    let (result, _) = run_tx(&mut db, &TESTBENCH_ADDR, calldata, U256::from(100));
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(100));
+    assert_eq!(db.accounts[&TESTBENCH_ADDR].info.balance, U256::from(100));
+
+    let args = (RECIPIENT, U256::from(40)).abi_encode();
+    let result = call_testbench(&mut db, "send(address,uint256)", args);
+    assert!(bool::abi_decode(&result, true).unwrap());
+    assert_eq!(db.accounts[&TESTBENCH_ADDR].info.balance, U256::from(60));
+    assert_eq!(db.accounts[&RECIPIENT].info.balance, U256::from(40));
+}
+
+fn test_panics() {
+    let mut db = testbench_db();
+    add_riscv_contract_to_db(&mut db, ERC20_ADDR, "erc20");
+    let revert_data = |db: &mut InMemoryDB, addr: Address, calldata: Vec<u8>| {
+        let result = try_run_tx(db, &addr, calldata, U256::ZERO);
+        match result {
+            Err(ExecutionResult::Revert { output, .. }) => output,
+            result => panic!("Expected a revert, got {:?}", result),
+        }
+    };
+    let panic_code = |output: Bytes| Panic::abi_decode(&output, true).unwrap().code;
+
+    // `checked_add` reverts with `Panic(0x11)`, like Solidity's checked arithmetic.
+    let owner = address!("00000000000000000000000000000000000000ee");
+    let signature = "mint(address,uint256)";
+    call_method(
+        &mut db,
+        ERC20_ADDR,
+        signature,
+        (owner, U256::MAX).abi_encode(),
+    );
+    let calldata = encode_call(signature, (owner, U256::from(1)).abi_encode());
+    let output = revert_data(&mut db, ERC20_ADDR, calldata);
+    assert_eq!(panic_code(output), U256::from(0x11));
+
+    // Calldata without a full selector reverts without data, and arguments that don't decode
+    // revert with an `Error(string)`.
+    let output = revert_data(&mut db, ERC20_ADDR, vec![0xa9, 0x05]);
+    assert!(output.is_empty());
+    let calldata = encode_call(signature, owner.abi_encode());
+    let output = revert_data(&mut db, ERC20_ADDR, calldata);
+    let reason = Revert::abi_decode(&output, true).unwrap().reason;
+    assert!(reason.starts_with("invalid calldata"));
+
+    // Panics of the compiled contract map to the codes Solidity uses for the same failures.
+    let signature = "fail(uint8,uint64)";
+    for (kind, value, code) in [
+        (0u8, 1u64, 0x11),
+        (1, 0, 0x12),
+        (2, 0, 0x12),
+        (3, 3, 0x32),
+        (4, 1, 0x01),
+    ] {
+        let calldata = encode_call(signature, (kind, value).abi_encode());
+        let output = revert_data(&mut db, TESTBENCH_ADDR, calldata);
+        assert_eq!(panic_code(output), U256::from(code));
+    }
+    let calldata = encode_call(signature, (5u8, 0u64).abi_encode());
+    let output = revert_data(&mut db, TESTBENCH_ADDR, calldata);
+    let reason = Revert::abi_decode(&output, true).unwrap().reason;
+    assert_eq!(reason, "unknown failure");
+}
+
+fn test_account_introspection() {
+    const TOKEN_ADDR: Address = address!("5cad6fbc89acde08f5dc04f9fafd2cbdae9f8071");
+    const EMPTY_ADDR: Address = address!("00000000000000000000000000000000000000bb");
+    let mut db = testbench_db();
+    let code = add_riscv_contract_to_db(&mut db, TOKEN_ADDR, "erc20");
+    db.accounts.get_mut(&TESTBENCH_ADDR).unwrap().info.balance = U256::from(5);
+    db.accounts.get_mut(&TOKEN_ADDR).unwrap().info.balance = U256::from(123);
+
+    let result = call_testbench(&mut db, "balanceOf(address)", TOKEN_ADDR.abi_encode());
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(123));
+    let result = call_testbench(&mut db, "ownBalance()", Vec::new());
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(5));
+
+    for (addr, size, hash) in [
+        (TOKEN_ADDR, code.len() as u64, keccak256(&code)),
+        // Accounts that don't exist have no code, and a zero code hash.
+        (EMPTY_ADDR, 0, B256::ZERO),
+    ] {
+        let result = call_testbench(&mut db, "codeSize(address)", addr.abi_encode());
+        assert_eq!(u64::abi_decode(&result, true).unwrap(), size);
+        let result = call_testbench(&mut db, "codeHash(address)", addr.abi_encode());
+        assert_eq!(B256::abi_decode(&result, true).unwrap(), hash);
+    }
+
+    // Bytes past the end of the code are copied as zeros.
+    let mut code_end = code[code.len() - 2..].to_vec();
+    code_end.extend_from_slice(&[0, 0]);
+    for (offset, expected) in [(1, code[1..5].to_vec()), (code.len() as u64 - 2, code_end)] {
+        let args = (TOKEN_ADDR, offset, 4u64).abi_encode();
+        let result = call_testbench(&mut db, "codeCopy(address,uint64,uint64)", args);
+        assert_eq!(Bytes::abi_decode(&result, true).unwrap(), expected);
+    }
+}
+
+fn test_environment() {
+    const ORIGIN: Address = address!("0102030405060708090a0b0c0d0e0f1011121314");
+    const COINBASE: Address = address!("f1f2f3f4f5f6f7f8f9fafbfcfdfeff0001020304");
+    let prevrandao = b256!("00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff");
+    let mut db = testbench_db();
+    let origin = AccountInfo {
+        balance: U256::from(10).pow(U256::from(18)),
+        ..Default::default()
+    };
+    db.insert_account_info(ORIGIN, origin);
+
+    let set_env = |env: &mut Env| {
+        env.tx.caller = ORIGIN;
+        env.tx.gas_limit = 30_000_000;
+        env.tx.gas_price = U256::from(10);
+        // Larger than a `u64`, so it is truncated to `u64::MAX`.
+        env.block.number = U256::MAX;
+        env.block.timestamp = U256::from(1_700_000_000);
+        env.block.coinbase = COINBASE;
+        env.block.gas_limit = U256::from(30_000_000);
+        env.block.basefee = U256::from(7);
+        env.block.difficulty = U256::from(0x1234);
+        env.block.prevrandao = Some(prevrandao);
+        env.cfg.chain_id = 4242;
+    };
+    let run = |db: &mut InMemoryDB, signature: &str, spec_id: SpecId| {
+        let calldata = encode_call(signature, Vec::new());
+        try_run_tx_with_env(db, &TESTBENCH_ADDR, calldata, U256::ZERO, spec_id, set_env)
+    };
+    type Environment = (Address, U256, u64, u64, u64, Address, u64, B256);
+
+    let (result, _) = run(&mut db, "environment()", SpecId::LATEST).unwrap();
+    let environment = Environment::abi_decode_params(&result, true).unwrap();
+    assert_eq!(
+        environment,
+        (
+            ORIGIN,
+            U256::from(10),
+            u64::MAX,
+            1_700_000_000,
+            4242,
+            COINBASE,
+            30_000_000,
+            prevrandao,
+        )
+    );
+    let (result, _) = run(&mut db, "baseFee()", SpecId::LATEST).unwrap();
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(7));
+
+    // Before the merge, PREVRANDAO returned the block difficulty.
+    let (result, _) = run(&mut db, "environment()", SpecId::BERLIN).unwrap();
+    let (.., randao) = Environment::abi_decode_params(&result, true).unwrap();
+    assert_eq!(U256::from_be_bytes(randao.0), U256::from(0x1234));
+
+    // BASEFEE only exists since London.
+    let result = run(&mut db, "baseFee()", SpecId::BERLIN);
+    assert!(matches!(
+        result,
+        Err(ExecutionResult::Halt {
+            reason: HaltReason::NotActivated,
+            ..
+        })
+    ));
+}
+
+fn test_precompiles() {
+    let mut db = testbench_db();
+
+    let data = Bytes::from_static(b"abc");
+    let result = call_testbench(&mut db, "sha256Hash(bytes)", (data,).abi_encode_params());
+    assert_eq!(
+        B256::abi_decode(&result, true).unwrap(),
+        b256!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+    );
+
+    // A valid signature from the ecrecover tests of go-ethereum, and the same one with an invalid
+    // recovery id.
+    let hash = b256!("38d18acb67d25c8bb9942764b62f18e17054f66a817bd4295423adf9ed98873e");
+    let r = hash;
+    let s = b256!("789d1dd423d25f0772d2748d60f7e4b81bb14d086eba8e8e8efb6dcff8a4ae02");
+    let signature = "recoverSigner(bytes32,uint8,bytes32,bytes32)";
+    let result = call_testbench(&mut db, signature, (hash, 27u8, r, s).abi_encode());
+    assert_eq!(
+        Address::abi_decode(&result, true).unwrap(),
+        address!("ceaccac640adf55b2028469bd36ba501f28b699d")
+    );
+    let result = call_testbench(&mut db, signature, (hash, 29u8, r, s).abi_encode());
+    assert_eq!(Address::abi_decode(&result, true).unwrap(), Address::ZERO);
+
+    let data = Bytes::from_static(b"abc");
+    let result = call_testbench(&mut db, "ripemd160Hash(bytes)", (data,).abi_encode_params());
+    assert_eq!(
+        FixedBytes::<20>::abi_decode(&result, true).unwrap(),
+        fixed_bytes!("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc")
+    );
+
+    // From EIP-198: by Fermat's little theorem, `3 ** (p - 1) % p` is one for the prime `p`.
+    let base = Bytes::from_static(&[3]);
+    let exponent = b256!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2e");
+    let modulus = b256!("fffffffffffffffffffffffffffffffffffffffffffffffffffffffefffffc2f");
+    let (exponent, modulus) = (exponent.to_vec(), modulus.to_vec());
+    let args = (base, Bytes::from(exponent), Bytes::from(modulus)).abi_encode_params();
+    let result = call_testbench(&mut db, "modExp(bytes,bytes,bytes)", args);
+    assert_eq!(
+        Bytes::abi_decode(&result, true).unwrap(),
+        Bytes::from(U256::from(1).to_be_bytes_vec())
+    );
+
+    // The generators of bn254 and of its twist, and twice the first one.
+    let word = |hex: &str| hex.parse::<U256>().unwrap();
+    let g1 = [U256::from(1), U256::from(2)];
+    let g1_double = [
+        word("0x030644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd3"),
+        word("0x15ed738c0e0a7c92e7845f96b2ae9c0a68a6a449e3538fc7ff3ebf7a5a18a2c4"),
+    ];
+    let g2 = [
+        word("0x198e9393920d483a7260bfb731fb5d25f1aa493335a9e71297e485b7aef312c2"),
+        word("0x1800deef121f1e76426a00665e5c4479674322d4f75edadd46debd5cd992f6ed"),
+        word("0x090689d0585ff075ec9e99ad690c3395bc4b313370b38ef355acdadcd122975b"),
+        word("0x12c85ea5db8c6deb4aab71808dcb408fe3d1e7690c43d37b4ce6cc0166fa7daa"),
+    ];
+    let args = (g1, g1).abi_encode_params();
+    let result = call_testbench(&mut db, "addPoints(uint256[2],uint256[2])", args);
+    assert_eq!(<[U256; 2]>::abi_decode(&result, true).unwrap(), g1_double);
+    let args = (g1, U256::from(2)).abi_encode_params();
+    let result = call_testbench(&mut db, "mulPoint(uint256[2],uint256)", args);
+    assert_eq!(<[U256; 2]>::abi_decode(&result, true).unwrap(), g1_double);
+
+    // `e(g1, g2) * e(-g1, g2)` is one, while `e(g1, g2)` alone is not.
+    let minus_g1 = [
+        U256::from(1),
+        word("0x30644e72e131a029b85045b68181585d97816a916871ca8d3c208c16d87cfd45"),
+    ];
+    let signature = "pairing((uint256[2],uint256[4])[])";
+    let pairs = vec![(g1, g2), (minus_g1, g2)];
+    let result = call_testbench(&mut db, signature, (pairs,).abi_encode_params());
+    assert!(bool::abi_decode(&result, true).unwrap());
+    let pairs = vec![(g1, g2)];
+    let result = call_testbench(&mut db, signature, (pairs,).abi_encode_params());
+    assert!(!bool::abi_decode(&result, true).unwrap());
+
+    // Test vector 5 of EIP-152: the only block of the BLAKE2b-512 hash of "abc".
+    let h: [u64; 8] = [
+        0x6a09e667f2bdc948,
+        0xbb67ae8584caa73b,
+        0x3c6ef372fe94f82b,
+        0xa54ff53a5f1d36f1,
+        0x510e527fade682d1,
+        0x9b05688c2b3e6c1f,
+        0x1f83d9abfb41bd6b,
+        0x5be0cd19137e2179,
+    ];
+    let mut m = [0u64; 16];
+    m[0] = 0x636261;
+    let args = (12u32, h, m, [3u64, 0], true).abi_encode_params();
+    let signature = "blake2Compress(uint32,uint64[8],uint64[16],uint64[2],bool)";
+    let result = call_testbench(&mut db, signature, args);
+    let state = <[u64; 8]>::abi_decode(&result, true).unwrap();
+    let hash: Vec<u8> = state.iter().flat_map(|word| word.to_le_bytes()).collect();
+    assert_eq!(
+        FixedBytes::<64>::from_slice(&hash),
+        fixed_bytes!("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923")
+    );
+}
+
+fn test_transient_storage() {
+    let mut db = testbench_db();
+
+    // The flag set by the outer call is visible to the nested call in the same transaction...
+    let account = address!("00000000000000000000000000000000000000cc");
+    let args = (account, TESTBENCH_ADDR).abi_encode();
+    let result = call_testbench(&mut db, "setFlagAndRead(address,address)", args);
+    assert!(bool::abi_decode(&result, true).unwrap());
+
+    // ...and cleared at the end of it.
+    let result = call_testbench(&mut db, "flag(address)", account.abi_encode());
+    assert!(!bool::abi_decode(&result, true).unwrap());
+}
+
+fn test_selfdestruct() {
+    const BENEFICIARY: Address = address!("00000000000000000000000000000000000000dd");
+    let mut db = testbench_db();
+    db.accounts.get_mut(&TESTBENCH_ADDR).unwrap().info.balance = U256::from(100);
+
+    // A contract destroyed in the transaction that created it is deleted, and its balance sent to
+    // the beneficiary.
+    let mut init_code = vec![0xff];
+    init_code.extend_from_slice(&compile_deploy("testbench").unwrap());
+    let args = (Bytes::from(init_code), U256::from(30), BENEFICIARY).abi_encode_params();
+    let result = call_testbench(&mut db, "deployAndDestroy(bytes,uint256,address)", args);
+    let child = Address::abi_decode(&result, true).unwrap();
+    let result = call_testbench(&mut db, "codeSize(address)", child.abi_encode());
+    assert_eq!(u64::abi_decode(&result, true).unwrap(), 0);
+    assert_eq!(db.accounts[&BENEFICIARY].info.balance, U256::from(30));
+
+    // In a later transaction, a contract only sends its remaining balance: the code and storage
+    // are kept.
+    call_testbench(&mut db, "setNumber(uint256)", U256::from(5).abi_encode());
+    call_testbench(&mut db, "destroy(address)", BENEFICIARY.abi_encode());
+    assert_eq!(db.accounts[&TESTBENCH_ADDR].info.balance, U256::ZERO);
+    assert_eq!(db.accounts[&BENEFICIARY].info.balance, U256::from(100));
+    let result = call_testbench(&mut db, "number()", Vec::new());
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(5));
+}
+
+fn test_gas_limited_call() {
+    let mut db = testbench_db();
+    let signature = "callWithGas(address,bytes,uint64)";
+
+    let calldata = Bytes::from(encode_call("number()", Vec::new()));
+    let args = (TESTBENCH_ADDR, calldata, 100_000u64).abi_encode_params();
+    let result = call_testbench(&mut db, signature, args);
+    let (success, _) = <(bool, u64)>::abi_decode_params(&result, true).unwrap();
+    assert!(success);
+
+    // The callee runs out of the gas it was given, while the caller keeps the rest and goes on.
+    let calldata = Bytes::from(encode_call("spin()", Vec::new()));
+    let args = (TESTBENCH_ADDR, calldata, 10_000u64).abi_encode_params();
+    let result = call_testbench(&mut db, signature, args);
+    let (success, gas_left) = <(bool, u64)>::abi_decode_params(&result, true).unwrap();
+    assert!(!success);
+    assert!(gas_left > 1_000_000);
+}
+
+fn test_return_data_copy() {
+    let mut db = testbench_db();
+    call_testbench(&mut db, "setNumber(uint256)", U256::from(42).abi_encode());
+    let signature = "copyReturnData(address,bytes,uint64,uint64)";
+    let calldata = Bytes::from(encode_call("number()", Vec::new()));
+
+    let args = (TESTBENCH_ADDR, calldata.clone(), 16u64, 16u64).abi_encode_params();
+    let result = call_testbench(&mut db, signature, args);
+    let copied = Bytes::abi_decode(&result, true).unwrap();
+    assert_eq!(copied.as_ref(), &U256::from(42).to_be_bytes::<32>()[16..]);
+
+    // Reading past the end of the returned data halts, like `RETURNDATACOPY`.
+    let args = (TESTBENCH_ADDR, calldata, 16u64, 17u64).abi_encode_params();
+    let result = try_run_tx(
+        &mut db,
+        &TESTBENCH_ADDR,
+        encode_call(signature, args),
+        U256::ZERO,
+    );
+    assert!(matches!(
+        result,
+        Err(ExecutionResult::Halt {
+            reason: HaltReason::OutOfOffset,
+            ..
+        })
+    ));
+}
+
+fn test_storage_layout() {
+    let mut db = testbench_db();
+    add_riscv_contract_to_db(&mut db, ERC20_ADDR, "erc20");
+
+    // Slots 0 and 1 hold `number` and `flags`. Like in Solidity, the two `uint64`s share slot 2,
+    // the address doesn't fit in the rest of it and takes slot 3, and `big` takes slot 4.
+    let owner = address!("00000000000000000000000000000000000000ee");
+    let big = U256::from_be_bytes(keccak256(b"big").0);
+    let pinned = U256::from(0xabcd);
+    call_testbench(
+        &mut db,
+        "setLayout(uint64,uint64,address,uint256,uint256)",
+        (1u64, 2u64, owner, big, pinned).abi_encode_params(),
+    );
+    let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+    assert_eq!(
+        storage[&U256::from(2)],
+        (U256::from(2) << 64) | U256::from(1)
+    );
+    assert_eq!(
+        storage[&U256::from(3)],
+        U256::from_be_bytes(owner.into_word().0)
+    );
+    assert_eq!(storage[&U256::from(4)], big);
+    // `#[slot(10)]` moves the field to slot 10.
+    assert_eq!(storage[&U256::from(10)], pinned);
+
+    // The balance of `owner` in the mapping at slot 0 is at keccak256(pad32(owner) ++ pad32(0)).
+    call_method(
+        &mut db,
+        ERC20_ADDR,
+        "mint(address,uint256)",
+        (owner, U256::from(42)).abi_encode(),
+    );
+    let mut key = owner.into_word().to_vec();
+    key.extend_from_slice(&[0; 32]);
+    let slot = U256::from_be_bytes(keccak256(&key).0);
+    assert_eq!(db.accounts[&ERC20_ADDR].storage[&slot], U256::from(42));
+
+    // A `Position` in the mapping at slot 11 takes three slots, one per field, like the equivalent
+    // Solidity struct.
+    let (amount, debt, last_update) = (U256::from(1000), U256::from(300), 1_700_000_000u64);
+    call_testbench(
+        &mut db,
+        "setPosition(address,uint256,uint256,uint64)",
+        (owner, amount, debt, last_update).abi_encode_params(),
+    );
+    let mut key = owner.into_word().to_vec();
+    key.extend_from_slice(&U256::from(11).to_be_bytes::<32>());
+    let base = U256::from_be_bytes(keccak256(&key).0);
+    let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+    assert_eq!(storage[&base], amount);
+    assert_eq!(storage[&(base + U256::from(1))], debt);
+    assert_eq!(storage[&(base + U256::from(2))], U256::from(last_update));
+    let result = call_testbench(&mut db, "position(address)", owner.abi_encode());
+    assert_eq!(
+        <(U256, U256, u64)>::abi_decode_params(&result, true).unwrap(),
+        (amount, debt, last_update)
+    );
+}
+
+fn test_dynamic_storage_layout() {
+    let mut db = testbench_db();
+    let slot_value = |db: &InMemoryDB, slot: U256| {
+        let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+        storage.get(&slot).copied().unwrap_or_default()
+    };
+    let data_slot =
+        |slot: u64| U256::from_be_bytes(keccak256(U256::from(slot).to_be_bytes::<32>()).0);
+    // Like Solidity, short arrays are stored left-aligned with twice their length in the last byte.
+    let short = |data: &[u8]| {
+        let mut word = [0u8; 32];
+        word[..data.len()].copy_from_slice(data);
+        word[31] = (data.len() * 2) as u8;
+        U256::from_be_bytes(word)
+    };
+    let left_aligned = |data: &[u8]| {
+        let mut word = [0u8; 32];
+        word[..data.len()].copy_from_slice(data);
+        U256::from_be_bytes(word)
+    };
+
+    // The `uint64[]` at slot 13 stores its length there, and packs four elements per slot from
+    // `keccak256(13)` on, starting from the lowest-order bytes.
+    for value in 1..=5u64 {
+        call_testbench(&mut db, "pushNumber(uint64)", value.abi_encode());
+    }
+    let base = data_slot(13);
+    let first_four =
+        U256::from(1) | (U256::from(2) << 64) | (U256::from(3) << 128) | (U256::from(4) << 192);
+    assert_eq!(slot_value(&db, U256::from(13)), U256::from(5));
+    assert_eq!(slot_value(&db, base), first_four);
+    assert_eq!(slot_value(&db, base + U256::from(1)), U256::from(5));
+    let result = call_testbench(&mut db, "numberAt(uint64)", 4u64.abi_encode());
+    assert_eq!(u64::abi_decode(&result, true).unwrap(), 5);
+
+    // Popping zeroes the element, and only it.
+    for expected in [5u64, 4] {
+        let result = call_testbench(&mut db, "popNumber()", Vec::new());
+        assert_eq!(u64::abi_decode(&result, true).unwrap(), expected);
+    }
+    assert_eq!(slot_value(&db, U256::from(13)), U256::from(3));
+    assert_eq!(
+        slot_value(&db, base),
+        first_four & ((U256::from(1) << 192) - U256::from(1))
+    );
+    assert_eq!(slot_value(&db, base + U256::from(1)), U256::ZERO);
+
+    // Reading past the end reverts with `Panic(0x32)`, like in Solidity.
+    let calldata = encode_call("numberAt(uint64)", 3u64.abi_encode());
+    match try_run_tx(&mut db, &TESTBENCH_ADDR, calldata, U256::ZERO) {
+        Err(ExecutionResult::Revert { output, .. }) => {
+            let code = Panic::abi_decode(&output, true).unwrap().code;
+            assert_eq!(code, U256::from(0x32));
+        }
+        result => panic!("Expected a revert, got {:?}", result),
+    }
+
+    // Addresses don't pack, so the `address[]` at slot 14 takes a slot per element.
+    let accounts = [
+        address!("00000000000000000000000000000000000000a1"),
+        address!("00000000000000000000000000000000000000a2"),
+    ];
+    for account in accounts {
+        call_testbench(&mut db, "pushAccount(address)", account.abi_encode());
+    }
+    let base = data_slot(14);
+    assert_eq!(slot_value(&db, U256::from(14)), U256::from(2));
+    for (i, account) in accounts.iter().enumerate() {
+        let expected = U256::from_be_bytes(account.into_word().0);
+        assert_eq!(slot_value(&db, base + U256::from(i)), expected);
+    }
+    let result = call_testbench(&mut db, "popAccount()", Vec::new());
+    assert_eq!(Address::abi_decode(&result, true).unwrap(), accounts[1]);
+    assert_eq!(slot_value(&db, U256::from(14)), U256::from(1));
+    assert_eq!(slot_value(&db, base + U256::from(1)), U256::ZERO);
+    let result = call_testbench(&mut db, "accountAt(uint64)", 0u64.abi_encode());
+    assert_eq!(Address::abi_decode(&result, true).unwrap(), accounts[0]);
+
+    // The `string` at slot 15 stores up to 31 bytes in place. Longer strings store `2 * len + 1`
+    // there, and their bytes from `keccak256(15)` on, which a shorter string clears again.
+    let set_name = |db: &mut InMemoryDB, name: &str| {
+        call_testbench(
+            db,
+            "setName(string)",
+            (name.to_string(),).abi_encode_params(),
+        );
+        let result = call_testbench(db, "name()", Vec::new());
+        assert_eq!(String::abi_decode(&result, true).unwrap(), name);
+    };
+    let base = data_slot(15);
+    set_name(&mut db, "hello");
+    assert_eq!(slot_value(&db, U256::from(15)), short(b"hello"));
+
+    let long = "a string that takes more than one slot";
+    set_name(&mut db, long);
+    assert_eq!(
+        slot_value(&db, U256::from(15)),
+        U256::from(long.len() * 2 + 1)
+    );
+    assert_eq!(slot_value(&db, base), left_aligned(&long.as_bytes()[..32]));
+    assert_eq!(
+        slot_value(&db, base + U256::from(1)),
+        left_aligned(&long.as_bytes()[32..])
+    );
+
+    set_name(&mut db, "hi");
+    assert_eq!(slot_value(&db, U256::from(15)), short(b"hi"));
+    assert_eq!(slot_value(&db, base), U256::ZERO);
+    assert_eq!(slot_value(&db, base + U256::from(1)), U256::ZERO);
+
+    // The `bytes` at slot 16 follow the same layout, with 31 bytes still fitting in place.
+    let base = data_slot(16);
+    let data = Bytes::from(vec![0xab; 31]);
+    call_testbench(
+        &mut db,
+        "setData(bytes)",
+        (data.clone(),).abi_encode_params(),
+    );
+    assert_eq!(slot_value(&db, U256::from(16)), short(&data));
+    let result = call_testbench(&mut db, "data()", Vec::new());
+    assert_eq!(Bytes::abi_decode(&result, true).unwrap(), data);
+
+    let data = Bytes::from(vec![0xcd; 32]);
+    call_testbench(
+        &mut db,
+        "setData(bytes)",
+        (data.clone(),).abi_encode_params(),
+    );
+    assert_eq!(slot_value(&db, U256::from(16)), U256::from(65));
+    assert_eq!(slot_value(&db, base), left_aligned(&data));
+    let result = call_testbench(&mut db, "data()", Vec::new());
+    assert_eq!(Bytes::abi_decode(&result, true).unwrap(), data);
+}
+
+fn test_signed_storage() {
+    let mut db = testbench_db();
+
+    // The `int24` at slot 17 is stored in three bytes of two's complement...
+    call_testbench(&mut db, "setDelta(int32)", (-5i32).abi_encode());
+    let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+    assert_eq!(storage[&U256::from(17)], U256::from(0xfffffb));
+    let result = call_testbench(&mut db, "delta()", Vec::new());
+    assert_eq!(i32::abi_decode(&result, true).unwrap(), -5);
+
+    // ...and sign-extended to 32 bytes as a key of the mapping at slot 18.
+    let delta = I256::try_from(-2).unwrap();
+    let args = (-1i32, delta).abi_encode_params();
+    call_testbench(&mut db, "setDeltaOf(int32,int256)", args);
+    let mut key = vec![0xff; 32];
+    key.extend_from_slice(&U256::from(18).to_be_bytes::<32>());
+    let slot = U256::from_be_bytes(keccak256(&key).0);
+    let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+    assert_eq!(storage[&slot], U256::MAX - U256::from(1));
+    let result = call_testbench(&mut db, "deltaOf(int32)", (-1i32).abi_encode());
+    assert_eq!(I256::abi_decode(&result, true).unwrap(), delta);
+}
+
+fn test_mapping_lookups() {
+    let mut db = testbench_db();
+    let account = address!("00000000000000000000000000000000000000ee");
+
+    // Each lookup of the `mapping(address => uint64[])` at slot 19 reaches the same array, so
+    // both pushes are kept.
+    let args = (account, 7u64, 8u64).abi_encode_params();
+    call_testbench(&mut db, "queueTwice(address,uint64,uint64)", args);
+    let result = call_testbench(&mut db, "queue(address)", account.abi_encode());
+    assert_eq!(Vec::<u64>::abi_decode(&result, true).unwrap(), vec![7, 8]);
+
+    let mut key = account.into_word().to_vec();
+    key.extend_from_slice(&U256::from(19).to_be_bytes::<32>());
+    let slot = keccak256(&key);
+    let data_slot = U256::from_be_bytes(keccak256(slot).0);
+    let storage = &db.accounts[&TESTBENCH_ADDR].storage;
+    assert_eq!(storage[&U256::from_be_bytes(slot.0)], U256::from(2));
+    assert_eq!(storage[&data_slot], U256::from(7) | (U256::from(8) << 64));
+}
+
+fn test_reentrant_storage_cell() {
+    let mut db = testbench_db();
+
+    // The outer frame writes its first increment back before reentering, and loads the one made by
+    // the reentrant call before its second increment, so none of them is lost.
+    let args = TESTBENCH_ADDR.abi_encode();
+    call_testbench(&mut db, "incrementAndReenter(address)", args);
+    let result = call_testbench(&mut db, "counter()", Vec::new());
+    assert_eq!(U256::abi_decode(&result, true).unwrap(), U256::from(3));
+}
+
 fn main() {
     test_runtime_from_binary();
     test_deploy();
+    test_nested_calls();
+    test_events();
+    test_create();
+    test_delegate_call();
+    test_value_transfers();
+    test_panics();
+    test_account_introspection();
+    test_environment();
+    test_precompiles();
+    test_transient_storage();
+    test_selfdestruct();
+    test_gas_limited_call();
+    test_return_data_copy();
+    test_storage_layout();
+    test_dynamic_storage_layout();
+    test_signed_storage();
+    test_reentrant_storage_cell();
+    test_mapping_lookups();
 }
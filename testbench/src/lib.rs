@@ -0,0 +1,398 @@
+#![no_std]
+#![no_main]
+
+use core::default::Default;
+
+use contract_derive::{contract, interface, storage, StorageCodec};
+use eth_riscv_runtime::checked_add;
+use eth_riscv_runtime::precompiles::{
+    blake2f, bn254_add, bn254_mul, bn254_pairing, ecrecover, modexp, ripemd160, sha256,
+};
+use eth_riscv_runtime::types::{
+    Mapping, StorageBytes, StorageCell, StorageString, StorageValue, StorageVec, TransientMapping,
+};
+use eth_riscv_runtime::{balance, ext_code_copy, ext_code_hash, ext_code_size, self_balance};
+use eth_riscv_runtime::{
+    block_base_fee, block_coinbase, block_gas_limit, block_number, block_prevrandao,
+    block_timestamp, chain_id, tx_gas_price, tx_origin,
+};
+use eth_riscv_runtime::{
+    call, call_code, create, create2, delegate_call, gas_left, msg_value, return_data_copy,
+    selfdestruct, send_eth,
+};
+
+use alloy_core::primitives::aliases::I24;
+use alloy_core::primitives::{Address, Bytes, FixedBytes, B256, I256, U256};
+
+extern crate alloc;
+use alloc::{string::String, vec, vec::Vec};
+
+#[interface]
+trait ITestBench {
+    fn flag(&self, account: Address) -> bool;
+    fn destroy(&mut self, beneficiary: Address);
+    fn increment(&mut self);
+}
+
+#[derive(StorageCodec)]
+pub struct Position {
+    amount: U256,
+    debt: U256,
+    last_update: u64,
+}
+
+/// Exercises the runtime features that the ERC20 examples don't use, for the end-to-end tests.
+#[storage]
+pub struct TestBench {
+    number: StorageValue<U256>,
+    flags: TransientMapping<Address, bool>,
+    // Laid out like `uint64 smallA; uint64 smallB; address owner; uint256 big;` in Solidity.
+    small_a: StorageValue<u64>,
+    small_b: StorageValue<u64>,
+    owner: StorageValue<Address>,
+    big: StorageValue<U256>,
+    #[slot(10)]
+    pinned: StorageValue<U256>,
+    positions: Mapping<Address, Position>,
+    counter: StorageCell<U256>,
+    numbers: StorageVec<u64>,
+    accounts: StorageVec<Address>,
+    name: StorageString,
+    data: StorageBytes,
+    delta: StorageValue<I24>,
+    deltas: Mapping<I24, I256>,
+    queues: Mapping<Address, StorageVec<u64>>,
+}
+
+#[contract]
+impl TestBench {
+    pub fn number(&self) -> U256 {
+        self.number.read()
+    }
+
+    pub fn set_number(&self, number: U256) {
+        self.number.write(number);
+    }
+
+    pub fn set_layout(&self, small_a: u64, small_b: u64, owner: Address, big: U256, pinned: U256) {
+        self.small_a.write(small_a);
+        self.small_b.write(small_b);
+        self.owner.write(owner);
+        self.big.write(big);
+        self.pinned.write(pinned);
+    }
+
+    pub fn set_position(&self, account: Address, amount: U256, debt: U256, last_update: u64) {
+        let position = Position {
+            amount,
+            debt,
+            last_update,
+        };
+        self.positions.write(account, position);
+    }
+
+    pub fn position(&self, account: Address) -> (U256, U256, u64) {
+        let position = self.positions.read(account);
+        (position.amount, position.debt, position.last_update)
+    }
+
+    pub fn push_number(&mut self, value: u64) {
+        self.numbers.push(value);
+    }
+
+    pub fn pop_number(&mut self) -> u64 {
+        self.numbers.pop().unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn number_at(&self, index: u64) -> u64 {
+        self.numbers.read(index)
+    }
+
+    pub fn push_account(&mut self, account: Address) {
+        self.accounts.push(account);
+    }
+
+    pub fn pop_account(&mut self) -> Address {
+        self.accounts.pop().unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn account_at(&self, index: u64) -> Address {
+        self.accounts.read(index)
+    }
+
+    pub fn name(&self) -> String {
+        self.name.read()
+    }
+
+    pub fn set_name(&mut self, name: String) {
+        self.name.write(&name);
+    }
+
+    pub fn data(&self) -> Bytes {
+        self.data.read().into()
+    }
+
+    pub fn set_data(&mut self, data: Bytes) {
+        self.data.write(&data);
+    }
+
+    pub fn delta(&self) -> i32 {
+        i32::try_from(self.delta.read()).unwrap()
+    }
+
+    pub fn set_delta(&mut self, delta: i32) {
+        self.delta.write(I24::try_from(delta).unwrap());
+    }
+
+    pub fn delta_of(&self, key: i32) -> I256 {
+        self.deltas.read(I24::try_from(key).unwrap())
+    }
+
+    pub fn set_delta_of(&mut self, key: i32, delta: I256) {
+        self.deltas.write(I24::try_from(key).unwrap(), delta);
+    }
+
+    /// Pushes two values to the queue of `account`, each through its own lookup of the mapping.
+    pub fn queue_twice(&mut self, account: Address, first: u64, second: u64) {
+        self.queues.at(account).push(first);
+        self.queues.at(account).push(second);
+    }
+
+    pub fn queue(&self, account: Address) -> Vec<u64> {
+        let queue = self.queues.at(account);
+        (0..queue.len()).map(|i| queue.read(i)).collect()
+    }
+
+    pub fn counter(&self) -> U256 {
+        *self.counter
+    }
+
+    pub fn increment(&mut self) {
+        *self.counter = checked_add(*self.counter, U256::from(1));
+    }
+
+    /// Increments the counter before and after incrementing it again through a call to this
+    /// contract at `this`.
+    pub fn increment_and_reenter(&mut self, this: Address) {
+        *self.counter = checked_add(*self.counter, U256::from(1));
+        ITestBench::new(this)
+            .increment()
+            .unwrap_or_else(|_| revert(&[]));
+        *self.counter = checked_add(*self.counter, U256::from(1));
+    }
+
+    pub fn flag(&self, account: Address) -> bool {
+        self.flags.read(account)
+    }
+
+    /// Sets the flag of `account`, then reads it back through a call to this contract at `this`.
+    pub fn set_flag_and_read(&self, account: Address, this: Address) -> bool {
+        self.flags.write(account, true);
+        ITestBench::new(this)
+            .flag(account)
+            .unwrap_or_else(|_| revert(&[]))
+    }
+
+    #[payable]
+    pub fn deposit(&self) -> U256 {
+        msg_value()
+    }
+
+    pub fn send(&self, to: Address, value: U256) -> bool {
+        send_eth(to, value)
+    }
+
+    pub fn balance_of(&self, account: Address) -> U256 {
+        balance(account)
+    }
+
+    pub fn own_balance(&self) -> U256 {
+        self_balance()
+    }
+
+    pub fn code_size(&self, account: Address) -> u64 {
+        ext_code_size(account)
+    }
+
+    pub fn code_hash(&self, account: Address) -> B256 {
+        ext_code_hash(account)
+    }
+
+    pub fn code_copy(&self, account: Address, offset: u64, size: u64) -> Bytes {
+        let mut code = vec![0; size as usize];
+        ext_code_copy(account, &mut code, offset);
+        code.into()
+    }
+
+    /// Returns the transaction and block environment, except for the base fee.
+    pub fn environment(&self) -> (Address, U256, u64, u64, u64, Address, u64, B256) {
+        (
+            tx_origin(),
+            tx_gas_price(),
+            block_number(),
+            block_timestamp(),
+            chain_id(),
+            block_coinbase(),
+            block_gas_limit(),
+            block_prevrandao(),
+        )
+    }
+
+    pub fn base_fee(&self) -> U256 {
+        block_base_fee()
+    }
+
+    pub fn sha256_hash(&self, data: Bytes) -> B256 {
+        sha256(&data).unwrap_or_else(|| revert(&[]))
+    }
+
+    /// Returns the signer of `hash`, or zero if the signature is invalid.
+    pub fn recover_signer(&self, hash: B256, v: u8, r: B256, s: B256) -> Address {
+        ecrecover(hash, v, r, s).unwrap_or_default()
+    }
+
+    pub fn ripemd160_hash(&self, data: Bytes) -> FixedBytes<20> {
+        ripemd160(&data).unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn mod_exp(&self, base: Bytes, exponent: Bytes, modulus: Bytes) -> Bytes {
+        modexp(&base, &exponent, &modulus)
+            .unwrap_or_else(|| revert(&[]))
+            .into()
+    }
+
+    pub fn add_points(&self, p1: [U256; 2], p2: [U256; 2]) -> [U256; 2] {
+        bn254_add(p1, p2).unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn mul_point(&self, p: [U256; 2], scalar: U256) -> [U256; 2] {
+        bn254_mul(p, scalar).unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn pairing(&self, pairs: Vec<([U256; 2], [U256; 4])>) -> bool {
+        bn254_pairing(&pairs).unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn blake2_compress(
+        &self,
+        rounds: u32,
+        h: [u64; 8],
+        m: [u64; 16],
+        t: [u64; 2],
+        f: bool,
+    ) -> [u64; 8] {
+        blake2f(rounds, h, m, t, f).unwrap_or_else(|| revert(&[]))
+    }
+
+    /// Calls `target` with at most `gas`, returning whether the call succeeded and the gas left
+    /// afterwards.
+    pub fn call_with_gas(&self, target: Address, calldata: Bytes, gas: u64) -> (bool, u64) {
+        let success = call(
+            gas,
+            target.as_ptr() as u64,
+            U256::ZERO,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        )
+        .is_ok();
+        (success, gas_left())
+    }
+
+    /// Panics the way Rust reports the failure selected by `kind`, for `value` out of range.
+    pub fn fail(&self, kind: u8, value: u64) -> u64 {
+        match kind {
+            0 => value + u64::MAX,
+            1 => u64::MAX / value,
+            2 => u64::MAX % value,
+            3 => [1, 2, 3][value as usize],
+            4 => {
+                assert!(value == 0);
+                value
+            }
+            _ => panic!("unknown failure"),
+        }
+    }
+
+    /// Loops until it runs out of gas.
+    pub fn spin(&self) {
+        loop {
+            core::hint::spin_loop();
+        }
+    }
+
+    pub fn delegate_call_succeeds(&self, target: Address, calldata: Bytes) -> bool {
+        delegate_call(
+            u64::MAX,
+            target.as_ptr() as u64,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        )
+        .is_ok()
+    }
+
+    pub fn call_code_succeeds(&self, target: Address, calldata: Bytes) -> bool {
+        call_code(
+            u64::MAX,
+            target.as_ptr() as u64,
+            U256::ZERO,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        )
+        .is_ok()
+    }
+
+    /// Calls `target`, then copies `size` bytes of what it returned, starting at `offset`.
+    pub fn copy_return_data(
+        &self,
+        target: Address,
+        calldata: Bytes,
+        offset: u64,
+        size: u64,
+    ) -> Bytes {
+        let _ = call(
+            u64::MAX,
+            target.as_ptr() as u64,
+            U256::ZERO,
+            calldata.as_ptr() as u64,
+            calldata.len() as u64,
+            0,
+            0,
+        );
+        let mut data = vec![0u8; size as usize];
+        return_data_copy(&mut data, offset);
+        data.into()
+    }
+
+    pub fn deploy(&self, init_code: Bytes) -> Address {
+        create(U256::ZERO, &init_code).unwrap_or_else(|| revert(&[]))
+    }
+
+    pub fn destroy(&self, beneficiary: Address) {
+        selfdestruct(beneficiary)
+    }
+
+    /// Deploys `init_code` with `value` wei, then calls `destroy` on the new contract in the same
+    /// transaction.
+    pub fn deploy_and_destroy(
+        &self,
+        init_code: Bytes,
+        value: U256,
+        beneficiary: Address,
+    ) -> Address {
+        let child = create(value, &init_code).unwrap_or_else(|| revert(&[]));
+        ITestBench::new(child)
+            .destroy(beneficiary)
+            .unwrap_or_else(|_| revert(&[]));
+        child
+    }
+
+    pub fn deploy2(&self, init_code: Bytes, salt: B256) -> Address {
+        create2(U256::ZERO, &init_code, salt).unwrap_or_else(|| revert(&[]))
+    }
+}